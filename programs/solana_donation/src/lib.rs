@@ -1,9 +1,15 @@
 use anchor_lang::prelude::*;
 use anchor_spl::token::{Token, TokenAccount, Mint};
+use anchor_spl::associated_token::{AssociatedToken, get_associated_token_address};
 
 declare_id!("2qqDQ8RadpzattcT4mAcxuzrLjrvsmz3NXDqf72pmyYR");
 
 const ACTIVE_FUNDRAISINGS_LIMIT: usize = 100;
+const MAX_TITLE_LEN: usize = 64;
+const MAX_METADATA_URI_LEN: usize = 200;
+const MAX_CO_ORGANIZERS: usize = 5;
+const MAX_WITHDRAWAL_SIGNERS: usize = 5;
+const MAX_VESTING_TRANCHES: usize = 16;
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
 pub struct DonaterTopInfo {
@@ -15,15 +21,90 @@ impl DonaterTopInfo {
     pub const MAX_SIZE: usize = 8 + 32;
 }
 
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct CoOrganizer {
+    pub organizer: Pubkey,
+    pub can_update_metadata: bool,
+    pub can_withdraw: bool,
+    pub can_pause: bool,
+}
+
+impl CoOrganizer {
+    pub const MAX_SIZE: usize = 32 + 1 + 1 + 1;
+
+    pub fn has_role(&self, role: OrganizerRole) -> bool {
+        match role {
+            OrganizerRole::UpdateMetadata => self.can_update_metadata,
+            OrganizerRole::Withdraw => self.can_withdraw,
+            OrganizerRole::Pause => self.can_pause,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum OrganizerRole {
+    UpdateMetadata,
+    Withdraw,
+    Pause,
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
 pub struct ActiveFundraisingBalance {
     pub id: u64,
-    pub balance: u64
+    pub balance: u64,
+    // Share of canceled fundraisings held by the service account until the fundraising claims it
+    pub pending_redistribution: u64
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum RedistributionPolicy {
+    Proportional,
+    EqualSplit,
+    RefundDonaters,
+    ReserveFund,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FundraisingStatus {
+    Active,
+    Withdrawn,
+    Canceled,
+    Expired,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FundraisingCategory {
+    Medical,
+    Education,
+    Environment,
+    Animals,
+    Disaster,
+    Community,
+    Other,
+}
+
+impl FundraisingStatus {
+    // Every final status can only be reached from Active
+    pub fn can_transition_to(&self, next: FundraisingStatus) -> bool {
+        matches!(
+            (self, next),
+            (FundraisingStatus::Active, FundraisingStatus::Withdrawn)
+                | (FundraisingStatus::Active, FundraisingStatus::Canceled)
+                | (FundraisingStatus::Active, FundraisingStatus::Expired)
+        )
+    }
 }
 
 #[account]
 pub struct DonationService {
     pub owner: Pubkey,
+    pub guardian: Pubkey,
+    pub paused: bool,
+    pub verifier: Pubkey,
+    pub verified_organizers_only: bool,
+    pub creation_deposit_amount: u64,
+    pub creation_deposit_in_chrt: bool,
+    pub expiry_period_seconds: u64,
     pub fundraisings_num: u64,
     pub vouchers_num: u64,
     pub total_fee: u64,
@@ -32,10 +113,22 @@ pub struct DonationService {
     pub total_canceled_funds: u64,
     pub owner_fee_percent: u64,
     pub no_fee_chrt_threshold: u64,
-    pub cancel_chrt_threshold: u64,
+    pub cancel_quorum_bps: u64,
+    pub cancel_voting_period_seconds: u64,
+    pub cancel_grace_period_seconds: u64,
+    pub redistribution_policy: RedistributionPolicy,
+    pub reserve_fund: Pubkey,
     pub reward_period_seconds: u64,
     pub reward_chrt_amount: u64, 
-    pub reward_cooldown: u64,
+    pub next_reward_at: u64,
+    pub reward_epoch: u64,
+    pub permissionless_rewards: bool,
+    pub crank_tip_chrt_amount: u64,
+    pub vesting_cliff_seconds: u64,
+    pub vesting_duration_seconds: u64,
+    pub total_burned_no_fee_chrt: u64,
+    pub total_burned_cancel_chrt: u64,
+    pub total_burned_forfeited_chrt: u64,
     pub top_donaters: [Option<DonaterTopInfo>; 10],
     pub nominated_donaters: Box<[Option<DonaterTopInfo>; 10]>,
     pub active_fundraising_balances: Vec<ActiveFundraisingBalance>,
@@ -44,34 +137,75 @@ pub struct DonationService {
 }
 
 impl DonationService {
-    pub const MAX_SIZE: usize = 32 + 8*10 +  DonaterTopInfo::MAX_SIZE*10 * 2 + (4 + 16 * ACTIVE_FUNDRAISINGS_LIMIT) + 32 + 1;
+    pub const MAX_SIZE: usize = 32 + 32 + 1 + 32 + 1 + 8 + 1 + 8 + 8*21 + 1 + 1 + 32 + (1 + DonaterTopInfo::MAX_SIZE)*10 * 2 + (4 + 24 * ACTIVE_FUNDRAISINGS_LIMIT) + 32 + 1;
 }
 
 #[account]
 pub struct Fundraising {
     pub owner: Pubkey,
+    pub beneficiary: Pubkey,
+    pub pending_beneficiary: Option<Pubkey>,
     pub id: u64,
     pub total_sum: u64,
     pub total_no_fee_chrt_sum: u64,
-    pub total_cancel_chrt_sum: u64,
-    pub is_finished: bool,
+    pub total_staked_chrt: u64,
+    pub total_appeal_chrt: u64,
+    pub cancel_proposals_num: u64,
+    pub cancel_voting_ends_at: u64,
+    pub cancel_grace_period_seconds: u64,
+    pub total_donated: u64,
+    pub refund_pool: u64,
+    pub deposit_amount: u64,
+    pub deposit_in_chrt: bool,
+    pub last_activity_at: u64,
+    pub status: FundraisingStatus,
+    pub is_paused: bool,
+    pub verified: bool,
     pub top_donaters: [Option<DonaterTopInfo>; 3],
+    pub co_organizers: Vec<CoOrganizer>,
+    pub withdrawal_signers: Vec<Pubkey>,
+    pub withdrawal_threshold: u8,
+    pub category: FundraisingCategory,
+    pub content_hash: [u8; 32],
+    pub title: String,
+    pub metadata_uri: String,
     pub bump: u8
 }
 
 impl Fundraising {
-    pub const MAX_SIZE: usize = 32 + 8*4 + 1 + (1 + DonaterTopInfo::MAX_SIZE) * 3 + 1;
+    pub const MAX_SIZE: usize = 32 + 32 + (1 + 32) + 8*11 + 1 + 8 + 1 + 1 + 1 + (1 + DonaterTopInfo::MAX_SIZE) * 3 + (4 + CoOrganizer::MAX_SIZE * MAX_CO_ORGANIZERS) + (4 + 32 * MAX_WITHDRAWAL_SIGNERS) + 1 + 1 + 32 + (4 + MAX_TITLE_LEN) + (4 + MAX_METADATA_URI_LEN) + 1;
+
+    // Account is sized to the actual metadata instead of the maximum allowed one
+    pub fn size(title: &str, metadata_uri: &str) -> usize {
+        Self::MAX_SIZE - MAX_TITLE_LEN - MAX_METADATA_URI_LEN + title.len() + metadata_uri.len()
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.status == FundraisingStatus::Active
+    }
+
+    // Owner implicitly has every role
+    pub fn is_authorized(&self, key: Pubkey, role: OrganizerRole) -> bool {
+        key == self.owner || self.co_organizers.iter().any(|x| x.organizer == key && x.has_role(role))
+    }
+
+    pub fn transition_to(&mut self, next: FundraisingStatus) -> Result<()> {
+        require!(self.status.can_transition_to(next), DonationError::InvalidStatusTransition);
+        self.status = next;
+        Ok(())
+    }
 }
 
 #[account]
 pub struct DonaterInfo {
     pub total_sum: u64,
     pub donater: Pubkey,
+    pub is_refunded: bool,
     pub bump: u8,
 }
 
 impl DonaterInfo {
-    pub const MAX_SIZE: usize = 8 + 32 + 1;
+    pub const MAX_SIZE: usize = 8 + 32 + 1 + 1;
 }
 
 #[account]
@@ -85,6 +219,214 @@ impl GlobalTopInfo {
     pub const MAX_SIZE: usize = 8 + 32 + 1;
 }
 
+#[account]
+pub struct RewardEpoch {
+    pub epoch: u64,
+    pub rewarded_at: u64,
+    pub reward_chrt_amount: u64,
+    pub rewarded_donaters: [Option<DonaterTopInfo>; 3],
+    pub bump: u8,
+}
+
+impl RewardEpoch {
+    pub const MAX_SIZE: usize = 8*3 + (1 + DonaterTopInfo::MAX_SIZE) * 3 + 1;
+}
+
+#[account]
+pub struct Organizer {
+    pub organizer: Pubkey,
+    pub verified: bool,
+    pub verified_until: u64,
+    pub verified_by: Pubkey,
+    pub bump: u8,
+}
+
+impl Organizer {
+    pub const MAX_SIZE: usize = 32 + 1 + 8 + 32 + 1;
+
+    pub fn is_verified(&self, current_time: u64) -> bool {
+        self.verified && current_time < self.verified_until
+    }
+}
+
+#[account]
+pub struct PendingReward {
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub total_claimed: u64,
+    pub bump: u8,
+}
+
+impl PendingReward {
+    pub const MAX_SIZE: usize = 32 + 8*2 + 1;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct VestingTranche {
+    pub amount: u64,
+    pub released: u64,
+    pub start: u64,
+}
+
+impl VestingTranche {
+    pub const MAX_SIZE: usize = 8*3;
+
+    pub fn vested(&self, cliff_seconds: u64, duration_seconds: u64, current_time: u64) -> u64 {
+        let elapsed = current_time.saturating_sub(self.start);
+        if elapsed < cliff_seconds {
+            0
+        } else if elapsed >= duration_seconds {
+            self.amount
+        } else {
+            (self.amount as u128 * elapsed as u128 / duration_seconds as u128) as u64
+        }
+    }
+}
+
+#[account]
+pub struct VestingAccount {
+    pub owner: Pubkey,
+    pub tranches: Vec<VestingTranche>,
+    pub vested_carry: u64,
+    pub total_released: u64,
+    pub bump: u8,
+}
+
+impl VestingAccount {
+    pub const MAX_SIZE: usize = 32 + (4 + VestingTranche::MAX_SIZE * MAX_VESTING_TRANCHES) + 8*2 + 1;
+
+    pub fn releasable(&self, cliff_seconds: u64, duration_seconds: u64, current_time: u64) -> Result<u64> {
+        self.tranches.iter()
+            .try_fold(self.vested_carry, |sum, tranche| tranche.vested(cliff_seconds, duration_seconds, current_time).checked_sub(tranche.released).and_then(|x| sum.checked_add(x)))
+            .ok_or_else(|| DonationError::MathOverflow.into())
+    }
+
+    pub fn mark_released(&mut self, cliff_seconds: u64, duration_seconds: u64, current_time: u64) {
+        for tranche in self.tranches.iter_mut() {
+            tranche.released = tranche.vested(cliff_seconds, duration_seconds, current_time);
+        }
+        self.tranches.retain(|x| x.released < x.amount);
+        self.vested_carry = 0;
+    }
+
+    // Every grant vests on its own schedule, so a new credit never delays the locked balance.
+    // Grants made within the same 1/MAX_VESTING_TRANCHES of the duration share a tranche to keep the account size fixed
+    pub fn credit(&mut self, amount: u64, cliff_seconds: u64, duration_seconds: u64, current_time: u64) -> Result<()> {
+        let mut vested_carry = self.vested_carry;
+        for tranche in self.tranches.iter().filter(|x| x.vested(cliff_seconds, duration_seconds, current_time) == x.amount) {
+            vested_carry = vested_carry.checked_add(tranche.amount - tranche.released).ok_or(DonationError::MathOverflow)?;
+        }
+        self.vested_carry = vested_carry;
+        self.tranches.retain(|x| x.vested(cliff_seconds, duration_seconds, current_time) < x.amount);
+
+        // Tranches still vesting started less than a duration ago and at least tranche_seconds apart, so a slot is always free here
+        let tranche_seconds = duration_seconds.checked_add(MAX_VESTING_TRANCHES as u64 - 1).ok_or(DonationError::MathOverflow)? / MAX_VESTING_TRANCHES as u64;
+        let is_full = self.tranches.len() >= MAX_VESTING_TRANCHES;
+        match self.tranches.last_mut() {
+            Some(last) if is_full || current_time < last.start.saturating_add(tranche_seconds) => {
+                last.amount = last.amount.checked_add(amount).ok_or(DonationError::MathOverflow)?;
+            }
+            _ => self.tranches.push(VestingTranche { amount, released: 0, start: current_time }),
+        }
+        Ok(())
+    }
+}
+
+#[account]
+pub struct ChrtStake {
+    pub owner: Pubkey,
+    pub fundraising_id: u64,
+    pub amount: u64,
+    pub bump: u8,
+}
+
+impl ChrtStake {
+    pub const MAX_SIZE: usize = 32 + 8*2 + 1;
+}
+
+#[account]
+pub struct CancelProposal {
+    pub fundraising_id: u64,
+    pub proposal_id: u64,
+    pub proposer: Pubkey,
+    pub voting_ends_at: u64,
+    pub grace_ends_at: u64,
+    pub chrt_supply: u64,
+    pub cancel_votes: u64,
+    pub keep_votes: u64,
+    pub appeal_stake: u64,
+    pub is_appeal_withdrawn: bool,
+    pub is_executed: bool,
+    pub bump: u8,
+}
+
+impl CancelProposal {
+    pub const MAX_SIZE: usize = 8*2 + 32 + 8*6 + 1 + 1 + 1;
+
+    pub fn has_quorum(&self, quorum_bps: u64) -> bool {
+        (self.cancel_votes as u128 + self.keep_votes as u128) * 10_000 >= self.chrt_supply as u128 * quorum_bps as u128
+    }
+
+    pub fn is_voted_for(&self, quorum_bps: u64) -> bool {
+        self.has_quorum(quorum_bps) && self.cancel_votes > self.keep_votes
+    }
+
+    // Organizer blocks the cancellation by matching the whole cancel side with own CHRT
+    pub fn is_blocked(&self) -> bool {
+        self.appeal_stake > 0 && self.appeal_stake >= self.cancel_votes
+    }
+
+    pub fn is_passed(&self, quorum_bps: u64) -> bool {
+        self.is_voted_for(quorum_bps) && !self.is_blocked()
+    }
+}
+
+#[account]
+pub struct CancelVote {
+    pub voter: Pubkey,
+    pub cancel_amount: u64,
+    pub keep_amount: u64,
+    pub bump: u8,
+}
+
+impl CancelVote {
+    pub const MAX_SIZE: usize = 32 + 8*2 + 1;
+}
+
+#[account]
+pub struct WithdrawalRequest {
+    pub fundraising_id: u64,
+    pub proposer: Pubkey,
+    pub approvals: Vec<Pubkey>,
+    pub bump: u8,
+}
+
+impl WithdrawalRequest {
+    pub const MAX_SIZE: usize = 8 + 32 + (4 + 32 * MAX_WITHDRAWAL_SIGNERS) + 1;
+
+    // Approvals of signers removed after approving are not counted
+    pub fn approvals_num(&self, signers: &[Pubkey]) -> usize {
+        self.approvals.iter().filter(|x| signers.contains(x)).count()
+    }
+}
+
+#[account]
+pub struct Subscription {
+    pub donater: Pubkey,
+    pub fundraising_id: u64,
+    pub referrer: Pubkey,
+    pub amount: u64,
+    pub interval_seconds: u64,
+    pub next_payment_at: u64,
+    pub balance: u64,
+    pub payments_num: u64,
+    pub bump: u8,
+}
+
+impl Subscription {
+    pub const MAX_SIZE: usize = 32 + 8 + 32 + 8*5 + 1;
+}
+
 #[derive(Accounts)]
 pub struct Initialize<'info> {
     #[account(init, payer=owner, space=8 + DonationService::MAX_SIZE, seeds=[b"state"], bump)]
@@ -95,16 +437,175 @@ pub struct Initialize<'info> {
 }
 
 #[derive(Accounts)]
+pub struct SetGuardian<'info> {
+    #[account(constraint = owner.key() == donation_service.owner @ DonationError::NotOwner)]
+    pub owner: Signer<'info>,
+    #[account(mut, seeds=[b"state"], bump)]
+    pub donation_service: Account<'info, DonationService>,
+}
+
+#[derive(Accounts)]
+pub struct SetPaused<'info> {
+    pub authority: Signer<'info>,
+    #[account(mut, seeds=[b"state"], bump)]
+    pub donation_service: Account<'info, DonationService>,
+}
+
+#[derive(Accounts)]
+#[instruction(title: String, metadata_uri: String)]
 pub struct CreateFundraising<'info> {
     #[account(mut)]
     pub owner: Signer<'info>,
     #[account(mut)]
     pub donation_service: Account<'info, DonationService>,
-    #[account(init, payer=owner, space = 8 + Fundraising::MAX_SIZE, seeds=[b"fundraising", donation_service.fundraisings_num.to_le_bytes().as_ref()], bump)]
+    #[account(init, payer=owner, space = 8 + Fundraising::size(&title, &metadata_uri), seeds=[b"fundraising", donation_service.fundraisings_num.to_le_bytes().as_ref()], bump)]
+    pub fundraising: Box<Account<'info, Fundraising>>,
+    /// CHECK: Organizer record of the creator, may not exist for unverified organizers
+    #[account(seeds=[b"organizer", owner.key().as_ref()], bump)]
+    pub organizer: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>
+}
+
+#[derive(Accounts)]
+pub struct ConfigureCreationDeposit<'info> {
+    #[account(constraint = owner.key() == donation_service.owner @ DonationError::NotOwner)]
+    pub owner: Signer<'info>,
+    #[account(mut, seeds=[b"state"], bump)]
+    pub donation_service: Account<'info, DonationService>,
+}
+
+#[derive(Accounts)]
+pub struct ConfigureExpiry<'info> {
+    #[account(constraint = owner.key() == donation_service.owner @ DonationError::NotOwner)]
+    pub owner: Signer<'info>,
+    #[account(mut, seeds=[b"state"], bump)]
+    pub donation_service: Account<'info, DonationService>,
+}
+
+#[derive(Accounts)]
+pub struct ConfigureVerification<'info> {
+    #[account(constraint = owner.key() == donation_service.owner @ DonationError::NotOwner)]
+    pub owner: Signer<'info>,
+    #[account(mut, seeds=[b"state"], bump)]
+    pub donation_service: Account<'info, DonationService>,
+}
+
+#[derive(Accounts)]
+#[instruction(organizer: Pubkey)]
+pub struct VerifyOrganizer<'info> {
+    #[account(mut, constraint = authority.key() == donation_service.owner || authority.key() == donation_service.verifier @ DonationError::NotVerifier)]
+    pub authority: Signer<'info>,
+    #[account(seeds=[b"state"], bump)]
+    pub donation_service: Account<'info, DonationService>,
+    #[account(init_if_needed, seeds=[b"organizer", organizer.as_ref()], payer=authority, space=8+Organizer::MAX_SIZE, bump)]
+    pub organizer_account: Account<'info, Organizer>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(organizer: Pubkey)]
+pub struct RevokeOrganizer<'info> {
+    #[account(constraint = authority.key() == donation_service.owner || authority.key() == donation_service.verifier @ DonationError::NotVerifier)]
+    pub authority: Signer<'info>,
+    #[account(seeds=[b"state"], bump)]
+    pub donation_service: Account<'info, DonationService>,
+    #[account(mut, seeds=[b"organizer", organizer.as_ref()], bump=organizer_account.bump)]
+    pub organizer_account: Account<'info, Organizer>,
+}
+
+#[derive(Accounts)]
+#[instruction(fundraising_id: u64, title: String, metadata_uri: String)]
+pub struct UpdateFundraisingMetadata<'info> {
+    #[account(mut, constraint = fundraising.is_authorized(authority.key(), OrganizerRole::UpdateMetadata) @ DonationError::NotFundingOwner)]
+    pub authority: Signer<'info>,
+    #[account(mut, seeds=[b"fundraising", fundraising_id.to_le_bytes().as_ref()], bump,
+        realloc = 8 + Fundraising::size(&title, &metadata_uri), realloc::payer = authority, realloc::zero = false)]
     pub fundraising: Account<'info, Fundraising>,
+    #[account(seeds=[b"state"], bump)]
+    pub donation_service: Account<'info, DonationService>,
     pub system_program: Program<'info, System>
 }
 
+#[derive(Accounts)]
+#[instruction(fundraising_id: u64)]
+pub struct SetWithdrawalSigners<'info> {
+    pub owner: Signer<'info>,
+    #[account(mut, seeds=[b"fundraising", fundraising_id.to_le_bytes().as_ref()], bump, has_one = owner @ DonationError::NotFundingOwner)]
+    pub fundraising: Account<'info, Fundraising>,
+    #[account(seeds=[b"state"], bump)]
+    pub donation_service: Account<'info, DonationService>,
+}
+
+#[derive(Accounts)]
+#[instruction(fundraising_id: u64)]
+pub struct CreateWithdrawalRequest<'info> {
+    #[account(mut, constraint = fundraising.withdrawal_signers.contains(&proposer.key()) @ DonationError::NotWithdrawalSigner)]
+    pub proposer: Signer<'info>,
+    #[account(seeds=[b"fundraising", fundraising_id.to_le_bytes().as_ref()], bump)]
+    pub fundraising: Account<'info, Fundraising>,
+    #[account(seeds=[b"state"], bump)]
+    pub donation_service: Account<'info, DonationService>,
+    #[account(init, seeds=[b"withdrawal-request", fundraising_id.to_le_bytes().as_ref()], payer=proposer, space=8+WithdrawalRequest::MAX_SIZE, bump)]
+    pub withdrawal_request: Account<'info, WithdrawalRequest>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(fundraising_id: u64)]
+pub struct ApproveWithdrawal<'info> {
+    #[account(constraint = fundraising.withdrawal_signers.contains(&approver.key()) @ DonationError::NotWithdrawalSigner)]
+    pub approver: Signer<'info>,
+    #[account(seeds=[b"fundraising", fundraising_id.to_le_bytes().as_ref()], bump)]
+    pub fundraising: Account<'info, Fundraising>,
+    #[account(mut, seeds=[b"withdrawal-request", fundraising_id.to_le_bytes().as_ref()], bump=withdrawal_request.bump)]
+    pub withdrawal_request: Account<'info, WithdrawalRequest>,
+    #[account(seeds=[b"state"], bump)]
+    pub donation_service: Account<'info, DonationService>,
+}
+
+#[derive(Accounts)]
+#[instruction(fundraising_id: u64)]
+pub struct SetFundraisingPaused<'info> {
+    #[account(constraint = fundraising.is_authorized(authority.key(), OrganizerRole::Pause) @ DonationError::NotFundingOwner)]
+    pub authority: Signer<'info>,
+    #[account(mut, seeds=[b"fundraising", fundraising_id.to_le_bytes().as_ref()], bump)]
+    pub fundraising: Account<'info, Fundraising>,
+    #[account(seeds=[b"state"], bump)]
+    pub donation_service: Account<'info, DonationService>,
+}
+
+#[derive(Accounts)]
+#[instruction(fundraising_id: u64)]
+pub struct ManageCoOrganizers<'info> {
+    pub owner: Signer<'info>,
+    #[account(mut, seeds=[b"fundraising", fundraising_id.to_le_bytes().as_ref()], bump, has_one = owner @ DonationError::NotFundingOwner)]
+    pub fundraising: Account<'info, Fundraising>,
+    #[account(seeds=[b"state"], bump)]
+    pub donation_service: Account<'info, DonationService>,
+}
+
+#[derive(Accounts)]
+#[instruction(fundraising_id: u64)]
+pub struct ProposeBeneficiary<'info> {
+    pub owner: Signer<'info>,
+    #[account(mut, seeds=[b"fundraising", fundraising_id.to_le_bytes().as_ref()], bump, has_one = owner @ DonationError::NotFundingOwner)]
+    pub fundraising: Account<'info, Fundraising>,
+    #[account(seeds=[b"state"], bump)]
+    pub donation_service: Account<'info, DonationService>,
+}
+
+#[derive(Accounts)]
+#[instruction(fundraising_id: u64)]
+pub struct AcceptBeneficiary<'info> {
+    #[account(constraint = fundraising.pending_beneficiary == Some(new_beneficiary.key()) @ DonationError::NotPendingBeneficiary)]
+    pub new_beneficiary: Signer<'info>,
+    #[account(mut, seeds=[b"fundraising", fundraising_id.to_le_bytes().as_ref()], bump)]
+    pub fundraising: Account<'info, Fundraising>,
+    #[account(seeds=[b"state"], bump)]
+    pub donation_service: Account<'info, DonationService>,
+}
+
 #[derive(Accounts)]
 #[instruction(amount: u64, fundraising_id: u64)]
 pub struct Donate<'info> {
@@ -114,6 +615,8 @@ pub struct Donate<'info> {
     pub donater_info: Account<'info, DonaterInfo>,
     #[account(init_if_needed, seeds=[b"global-top-info", donater.key().as_ref()], payer=donater, space=8+GlobalTopInfo::MAX_SIZE, bump)]
     pub donater_top_info: Account<'info, GlobalTopInfo>,
+    #[account(init_if_needed, seeds=[b"pending-reward", donater.key().as_ref()], payer=donater, space=8+PendingReward::MAX_SIZE, bump)]
+    pub donater_pending_reward: Box<Account<'info, PendingReward>>,
     #[account(mut, seeds=[b"state"], bump)]
     pub donation_service: Box<Account<'info, DonationService>>,
     #[account(mut, seeds=[b"fundraising", fundraising_id.to_le_bytes().as_ref()], bump)]
@@ -122,6 +625,8 @@ pub struct Donate<'info> {
     pub chrt_mint: Account<'info, Mint>,
     #[account(mut, token::mint=chrt_mint)]
     pub referrer_chrt_account: Account<'info, TokenAccount>,
+    #[account(init_if_needed, seeds=[b"vesting", referrer_chrt_account.owner.as_ref()], payer=donater, space=8+VestingAccount::MAX_SIZE, bump)]
+    pub referrer_vesting: Box<Account<'info, VestingAccount>>,
 
     pub system_program: Program<'info, System>,
     pub token_program: Program<'info, Token>,
@@ -134,8 +639,8 @@ pub struct DonateCHRT<'info> {
     pub donater: Signer<'info>,
     #[account(mut, token::authority=donater)]
     pub donater_token_account: Account<'info, TokenAccount>,
-    #[account(mut, token::authority=fundraising)]
-    pub fundraising_token_account: Account<'info, TokenAccount>,
+    #[account(mut, address=donation_service.token_mint)]
+    pub chrt_mint: Account<'info, Mint>,
     #[account(mut, seeds=[b"fundraising", fundraising_id.to_le_bytes().as_ref()], bump)]
     pub fundraising: Account<'info, Fundraising>,
     #[account(mut, seeds=[b"state"], bump)]
@@ -146,63 +651,525 @@ pub struct DonateCHRT<'info> {
 
 #[derive(Accounts)]
 #[instruction(fundraising_id: u64)]
-pub struct Withdraw<'info> {
-    #[account(mut, seeds=[b"state"], bump)]
-    pub donation_service: Account<'info, DonationService>,
-    #[account(mut, seeds=[b"fundraising", fundraising_id.to_le_bytes().as_ref()], bump)]
-    pub fundraising: Account<'info, Fundraising>,
+pub struct CreateSubscription<'info> {
     #[account(mut)]
-    pub fundraising_owner: Signer<'info>,
+    pub donater: Signer<'info>,
+    #[account(init, payer=donater, space=8 + Subscription::MAX_SIZE, seeds=[b"subscription", fundraising_id.to_le_bytes().as_ref(), donater.key().as_ref()], bump)]
+    pub subscription: Account<'info, Subscription>,
+    #[account(init_if_needed, payer=donater, space = 8 + DonaterInfo::MAX_SIZE, seeds = [b"donater-info", fundraising_id.to_le_bytes().as_ref(), donater.key().as_ref()], bump)]
+    pub donater_info: Account<'info, DonaterInfo>,
+    #[account(init_if_needed, seeds=[b"global-top-info", donater.key().as_ref()], payer=donater, space=8+GlobalTopInfo::MAX_SIZE, bump)]
+    pub donater_top_info: Account<'info, GlobalTopInfo>,
+    #[account(init_if_needed, seeds=[b"pending-reward", donater.key().as_ref()], payer=donater, space=8+PendingReward::MAX_SIZE, bump)]
+    pub donater_pending_reward: Box<Account<'info, PendingReward>>,
+    #[account(seeds=[b"state"], bump)]
+    pub donation_service: Box<Account<'info, DonationService>>,
+    #[account(seeds=[b"fundraising", fundraising_id.to_le_bytes().as_ref()], bump=fundraising.bump)]
+    pub fundraising: Account<'info, Fundraising>,
+    #[account(address=donation_service.token_mint)]
+    pub chrt_mint: Account<'info, Mint>,
+    #[account(token::mint=chrt_mint)]
+    pub referrer_chrt_account: Account<'info, TokenAccount>,
+    #[account(init_if_needed, seeds=[b"vesting", referrer_chrt_account.owner.as_ref()], payer=donater, space=8+VestingAccount::MAX_SIZE, bump)]
+    pub referrer_vesting: Box<Account<'info, VestingAccount>>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct WithdrawFee<'info> {
+#[instruction(fundraising_id: u64)]
+pub struct ExecuteSubscription<'info> {
+    pub user: Signer<'info>,
+    #[account(mut, seeds=[b"subscription", fundraising_id.to_le_bytes().as_ref(), subscription.donater.as_ref()], bump=subscription.bump)]
+    pub subscription: Account<'info, Subscription>,
+    #[account(mut, seeds=[b"donater-info", fundraising_id.to_le_bytes().as_ref(), subscription.donater.as_ref()], bump=donater_info.bump)]
+    pub donater_info: Account<'info, DonaterInfo>,
+    #[account(mut, seeds=[b"global-top-info", subscription.donater.as_ref()], bump)]
+    pub donater_top_info: Account<'info, GlobalTopInfo>,
     #[account(mut, seeds=[b"state"], bump)]
-    pub donation_service: Account<'info, DonationService>,
+    pub donation_service: Box<Account<'info, DonationService>>,
+    #[account(mut, seeds=[b"fundraising", fundraising_id.to_le_bytes().as_ref()], bump=fundraising.bump)]
+    pub fundraising: Account<'info, Fundraising>,
+    #[account(mut, address=donation_service.token_mint)]
+    pub chrt_mint: Account<'info, Mint>,
+    /// CHECK: only used to derive the referrer's token account
+    #[account(address=subscription.referrer)]
+    pub referrer: UncheckedAccount<'info>,
+    // Derived from the referrer instead of being pinned, so a closed or replaced token account can't block the payments
+    #[account(mut, associated_token::mint=chrt_mint, associated_token::authority=referrer)]
+    pub referrer_chrt_account: Account<'info, TokenAccount>,
+    #[account(mut, seeds=[b"vesting", referrer.key().as_ref()], bump=referrer_vesting.bump)]
+    pub referrer_vesting: Box<Account<'info, VestingAccount>>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(fundraising_id: u64)]
+pub struct CancelSubscription<'info> {
     #[account(mut)]
-    pub donation_service_owner: Signer<'info>,
-    pub system_program: Program<'info, System>,
+    pub donater: Signer<'info>,
+    #[account(mut, close=donater, seeds=[b"subscription", fundraising_id.to_le_bytes().as_ref(), donater.key().as_ref()], bump=subscription.bump)]
+    pub subscription: Account<'info, Subscription>,
 }
 
 #[derive(Accounts)]
 #[instruction(fundraising_id: u64)]
-pub struct CancelFundraising<'info> {
+pub struct OpenCancelProposal<'info> {
     #[account(mut)]
-    pub user: Signer<'info>,
-    #[account(mut, seeds=[b"state"], bump)]
-    pub donation_service: Account<'info, DonationService>,
+    pub proposer: Signer<'info>,
     #[account(mut, seeds=[b"fundraising", fundraising_id.to_le_bytes().as_ref()], bump)]
     pub fundraising: Account<'info, Fundraising>,
+    #[account(init, payer=proposer, space=8 + CancelProposal::MAX_SIZE, seeds=[b"cancel-proposal", fundraising_id.to_le_bytes().as_ref(), fundraising.cancel_proposals_num.to_le_bytes().as_ref()], bump)]
+    pub cancel_proposal: Account<'info, CancelProposal>,
+    #[account(address=donation_service.token_mint)]
+    pub chrt_mint: Account<'info, Mint>,
+    #[account(seeds=[b"state"], bump)]
+    pub donation_service: Box<Account<'info, DonationService>>,
     pub system_program: Program<'info, System>
 }
 
 #[derive(Accounts)]
-pub struct RewardTopDonaters <'info> {
-    #[account(mut, seeds=[b"state"], bump)]
-    pub donation_service: Box<Account<'info, DonationService>>,
+#[instruction(fundraising_id: u64, proposal_id: u64)]
+pub struct VoteCancelProposal<'info> {
     #[account(mut)]
+    pub voter: Signer<'info>,
+    #[account(mut, token::mint=chrt_mint, token::authority=voter)]
+    pub voter_token_account: Account<'info, TokenAccount>,
+    #[account(address=donation_service.token_mint)]
     pub chrt_mint: Account<'info, Mint>,
+    #[account(mut, seeds=[b"cancel-proposal", fundraising_id.to_le_bytes().as_ref(), proposal_id.to_le_bytes().as_ref()], bump=cancel_proposal.bump)]
+    pub cancel_proposal: Box<Account<'info, CancelProposal>>,
+    #[account(init_if_needed, payer=voter, associated_token::mint=chrt_mint, associated_token::authority=cancel_proposal)]
+    pub proposal_token_account: Box<Account<'info, TokenAccount>>,
+    #[account(init_if_needed, payer=voter, space=8 + CancelVote::MAX_SIZE, seeds=[b"cancel-vote", cancel_proposal.key().as_ref(), voter.key().as_ref()], bump)]
+    pub cancel_vote: Box<Account<'info, CancelVote>>,
+    #[account(seeds=[b"fundraising", fundraising_id.to_le_bytes().as_ref()], bump)]
+    pub fundraising: Account<'info, Fundraising>,
+    #[account(seeds=[b"state"], bump)]
+    pub donation_service: Box<Account<'info, DonationService>>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub rent: Sysvar<'info, Rent>
+}
+
+#[derive(Accounts)]
+#[instruction(fundraising_id: u64, proposal_id: u64)]
+pub struct RefundVote<'info> {
+    #[account(mut)]
+    pub voter: Signer<'info>,
+    #[account(mut, token::mint=chrt_mint)]
+    pub voter_token_account: Account<'info, TokenAccount>,
+    #[account(mut, address=donation_service.token_mint)]
+    pub chrt_mint: Account<'info, Mint>,
+    #[account(seeds=[b"cancel-proposal", fundraising_id.to_le_bytes().as_ref(), proposal_id.to_le_bytes().as_ref()], bump=cancel_proposal.bump)]
+    pub cancel_proposal: Box<Account<'info, CancelProposal>>,
+    #[account(mut, associated_token::mint=chrt_mint, associated_token::authority=cancel_proposal)]
+    pub proposal_token_account: Box<Account<'info, TokenAccount>>,
+    #[account(mut, close=voter, seeds=[b"cancel-vote", cancel_proposal.key().as_ref(), voter.key().as_ref()], bump=cancel_vote.bump)]
+    pub cancel_vote: Account<'info, CancelVote>,
+    #[account(mut, seeds=[b"state"], bump)]
+    pub donation_service: Box<Account<'info, DonationService>>,
+    pub token_program: Program<'info, Token>
+}
+
+#[derive(Accounts)]
+#[instruction(fundraising_id: u64, proposal_id: u64)]
+pub struct AppealCancelProposal<'info> {
+    #[account(mut)]
+    pub organizer: Signer<'info>,
+    #[account(mut, token::mint=chrt_mint, token::authority=organizer)]
+    pub organizer_token_account: Account<'info, TokenAccount>,
+    #[account(address=donation_service.token_mint)]
+    pub chrt_mint: Account<'info, Mint>,
+    #[account(mut, seeds=[b"cancel-proposal", fundraising_id.to_le_bytes().as_ref(), proposal_id.to_le_bytes().as_ref()], bump=cancel_proposal.bump)]
+    pub cancel_proposal: Box<Account<'info, CancelProposal>>,
+    #[account(mut, associated_token::mint=chrt_mint, associated_token::authority=cancel_proposal)]
+    pub proposal_token_account: Box<Account<'info, TokenAccount>>,
+    #[account(mut, seeds=[b"fundraising", fundraising_id.to_le_bytes().as_ref()], bump)]
+    pub fundraising: Account<'info, Fundraising>,
+    #[account(seeds=[b"state"], bump)]
+    pub donation_service: Box<Account<'info, DonationService>>,
+    pub token_program: Program<'info, Token>
+}
+
+#[derive(Accounts)]
+#[instruction(amount: u64, fundraising_id: u64)]
+pub struct StakeCHRT<'info> {
+    #[account(mut)]
+    pub staker: Signer<'info>,
+    #[account(mut, token::authority=staker)]
+    pub staker_token_account: Account<'info, TokenAccount>,
+    #[account(address=donation_service.token_mint)]
+    pub chrt_mint: Account<'info, Mint>,
+    #[account(init_if_needed, payer=staker, associated_token::mint=chrt_mint, associated_token::authority=fundraising)]
+    pub fundraising_token_account: Account<'info, TokenAccount>,
+    #[account(init_if_needed, payer=staker, space=8 + ChrtStake::MAX_SIZE, seeds=[b"chrt-stake", fundraising_id.to_le_bytes().as_ref(), staker.key().as_ref()], bump)]
+    pub chrt_stake: Box<Account<'info, ChrtStake>>,
+    #[account(mut, seeds=[b"fundraising", fundraising_id.to_le_bytes().as_ref()], bump)]
+    pub fundraising: Account<'info, Fundraising>,
+    #[account(seeds=[b"state"], bump)]
+    pub donation_service: Box<Account<'info, DonationService>>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub rent: Sysvar<'info, Rent>
+}
+
+#[derive(Accounts)]
+#[instruction(fundraising_id: u64)]
+pub struct UnstakeCHRT<'info> {
+    #[account(mut)]
+    pub staker: Signer<'info>,
+    #[account(mut, token::mint=chrt_mint)]
+    pub staker_token_account: Account<'info, TokenAccount>,
+    #[account(address=donation_service.token_mint)]
+    pub chrt_mint: Account<'info, Mint>,
+    #[account(mut, associated_token::mint=chrt_mint, associated_token::authority=fundraising)]
+    pub fundraising_token_account: Account<'info, TokenAccount>,
+    #[account(mut, close=staker, seeds=[b"chrt-stake", fundraising_id.to_le_bytes().as_ref(), staker.key().as_ref()], bump=chrt_stake.bump)]
+    pub chrt_stake: Account<'info, ChrtStake>,
+    #[account(mut, seeds=[b"fundraising", fundraising_id.to_le_bytes().as_ref()], bump=fundraising.bump)]
+    pub fundraising: Account<'info, Fundraising>,
+    #[account(seeds=[b"state"], bump)]
+    pub donation_service: Box<Account<'info, DonationService>>,
+    pub token_program: Program<'info, Token>
+}
+
+#[derive(Accounts)]
+#[instruction(fundraising_id: u64)]
+pub struct Withdraw<'info> {
+    #[account(mut, seeds=[b"state"], bump)]
+    pub donation_service: Account<'info, DonationService>,
+    #[account(mut, seeds=[b"fundraising", fundraising_id.to_le_bytes().as_ref()], bump, has_one = beneficiary @ DonationError::InvalidBeneficiary)]
+    pub fundraising: Account<'info, Fundraising>,
+    /// CHECK: Only receives the withdrawn lamports, checked against the fundraising beneficiary
+    #[account(mut)]
+    pub beneficiary: UncheckedAccount<'info>,
+    #[account(constraint = authority.key() == fundraising.beneficiary || fundraising.is_authorized(authority.key(), OrganizerRole::Withdraw) @ DonationError::NotFundingOwner)]
+    pub authority: Signer<'info>,
+    /// CHECK: Only receives the returned creation deposit
+    #[account(mut, address=fundraising.owner)]
+    pub owner: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(fundraising_id: u64)]
+pub struct ExecuteWithdrawal<'info> {
+    pub withdraw: Withdraw<'info>,
+    #[account(seeds=[b"withdrawal-request", fundraising_id.to_le_bytes().as_ref()], bump=withdrawal_request.bump)]
+    pub withdrawal_request: Account<'info, WithdrawalRequest>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawFee<'info> {
+    #[account(mut, seeds=[b"state"], bump)]
+    pub donation_service: Account<'info, DonationService>,
+    #[account(mut)]
+    pub donation_service_owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(fundraising_id: u64, proposal_id: u64)]
+pub struct CancelFundraising<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(mut, seeds=[b"state"], bump)]
+    pub donation_service: Account<'info, DonationService>,
+    #[account(mut, seeds=[b"fundraising", fundraising_id.to_le_bytes().as_ref()], bump)]
+    pub fundraising: Account<'info, Fundraising>,
+    #[account(mut, seeds=[b"cancel-proposal", fundraising_id.to_le_bytes().as_ref(), proposal_id.to_le_bytes().as_ref()], bump=cancel_proposal.bump)]
+    pub cancel_proposal: Account<'info, CancelProposal>,
+    /// CHECK: only receives lamports, address is fixed at initialization
+    #[account(mut, address=donation_service.reserve_fund)]
+    pub reserve_fund: UncheckedAccount<'info>,
+    #[account(mut, address=donation_service.token_mint)]
+    pub chrt_mint: Box<Account<'info, Mint>>,
+    /// CHECK: fundraising ATA, only exists once a CHRT deposit was taken or CHRT was staked
+    #[account(mut, address=get_associated_token_address(&fundraising.key(), &donation_service.token_mint))]
+    pub fundraising_token_account: UncheckedAccount<'info>,
+    pub token_program: Program<'info, Token>
+}
+
+#[derive(Accounts)]
+#[instruction(fundraising_id: u64)]
+pub struct ExpireFundraising<'info> {
     #[account(mut)]
-    pub top_1_wallet: Account<'info, TokenAccount>,
-    #[account(mut)]
-    pub top_2_wallet: Account<'info, TokenAccount>,
-    #[account(mut)]
-    pub top_3_wallet: Account<'info, TokenAccount>,
+    pub user: Signer<'info>,
+    #[account(mut, seeds=[b"state"], bump)]
+    pub donation_service: Account<'info, DonationService>,
+    #[account(mut, close=owner, seeds=[b"fundraising", fundraising_id.to_le_bytes().as_ref()], bump=fundraising.bump)]
+    pub fundraising: Account<'info, Fundraising>,
+    /// CHECK: only receives the rent of the closed accounts
+    #[account(mut, address=fundraising.owner)]
+    pub owner: UncheckedAccount<'info>,
+    #[account(mut, address=donation_service.token_mint)]
+    pub chrt_mint: Box<Account<'info, Mint>>,
+    /// CHECK: fundraising ATA, only exists once a CHRT deposit was taken or CHRT was staked
+    #[account(mut, address=get_associated_token_address(&fundraising.key(), &donation_service.token_mint))]
+    pub fundraising_token_account: UncheckedAccount<'info>,
+    pub token_program: Program<'info, Token>
+}
+
+#[derive(Accounts)]
+#[instruction(fundraising_id: u64)]
+pub struct ClaimRefund<'info> {
+    #[account(mut)]
+    pub donater: Signer<'info>,
+    #[account(mut, seeds=[b"donater-info", fundraising_id.to_le_bytes().as_ref(), donater.key().as_ref()], bump)]
+    pub donater_info: Account<'info, DonaterInfo>,
+    #[account(mut, seeds=[b"fundraising", fundraising_id.to_le_bytes().as_ref()], bump)]
+    pub fundraising: Account<'info, Fundraising>,
+}
+
+#[derive(Accounts)]
+#[instruction(fundraising_id: u64)]
+pub struct ClaimRedistribution<'info> {
+    #[account(mut, seeds=[b"state"], bump)]
+    pub donation_service: Account<'info, DonationService>,
+    #[account(mut, seeds=[b"fundraising", fundraising_id.to_le_bytes().as_ref()], bump)]
+    pub fundraising: Account<'info, Fundraising>,
+}
 
-    #[account(mut, seeds=[b"global-top-info", top_1_wallet.owner.key().as_ref()], bump)]
+#[derive(Accounts)]
+pub struct RewardTopDonaters <'info> {
+    #[account(mut, seeds=[b"state"], bump)]
+    pub donation_service: Box<Account<'info, DonationService>>,
+    #[account(mut, address=donation_service.token_mint)]
+    pub chrt_mint: Account<'info, Mint>,
+    #[account(mut, seeds=[b"pending-reward", top_1_pending_reward.owner.as_ref()], bump=top_1_pending_reward.bump)]
+    pub top_1_pending_reward: Account<'info, PendingReward>,
+    #[account(mut, seeds=[b"pending-reward", top_2_pending_reward.owner.as_ref()], bump=top_2_pending_reward.bump)]
+    pub top_2_pending_reward: Account<'info, PendingReward>,
+    #[account(mut, seeds=[b"pending-reward", top_3_pending_reward.owner.as_ref()], bump=top_3_pending_reward.bump)]
+    pub top_3_pending_reward: Account<'info, PendingReward>,
+
+    #[account(mut, seeds=[b"global-top-info", top_1_pending_reward.owner.as_ref()], bump)]
     pub top_1_info: Account<'info, GlobalTopInfo>,
-    #[account(mut, seeds=[b"global-top-info", top_2_wallet.owner.key().as_ref()], bump)]
+    #[account(mut, seeds=[b"global-top-info", top_2_pending_reward.owner.as_ref()], bump)]
     pub top_2_info: Account<'info, GlobalTopInfo>,
-    #[account(mut, seeds=[b"global-top-info", top_3_wallet.owner.key().as_ref()], bump)]
+    #[account(mut, seeds=[b"global-top-info", top_3_pending_reward.owner.as_ref()], bump)]
     pub top_3_info: Account<'info, GlobalTopInfo>,
+    #[account(init, payer=cranker, space=8 + RewardEpoch::MAX_SIZE, seeds=[b"reward-epoch", donation_service.reward_epoch.to_le_bytes().as_ref()], bump)]
+    pub reward_epoch: Account<'info, RewardEpoch>,
+    #[account(mut, token::mint=chrt_mint)]
+    pub cranker_chrt_account: Account<'info, TokenAccount>,
 
     #[account(mut)]
-    pub owner: Signer<'info>,
+    pub cranker: Signer<'info>,
     pub system_program: Program<'info, System>,
     pub token_program: Program<'info, Token>
 }
 
+#[derive(Accounts)]
+pub struct ClaimRewards<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(mut, seeds=[b"pending-reward", user.key().as_ref()], bump=pending_reward.bump)]
+    pub pending_reward: Account<'info, PendingReward>,
+    #[account(seeds=[b"state"], bump)]
+    pub donation_service: Box<Account<'info, DonationService>>,
+    #[account(mut, address=donation_service.token_mint)]
+    pub chrt_mint: Account<'info, Mint>,
+    #[account(init_if_needed, payer=user, associated_token::mint=chrt_mint, associated_token::authority=user)]
+    pub user_chrt_account: Account<'info, TokenAccount>,
+    #[account(init_if_needed, seeds=[b"vesting", user.key().as_ref()], payer=user, space=8+VestingAccount::MAX_SIZE, bump)]
+    pub vesting_account: Box<Account<'info, VestingAccount>>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub rent: Sysvar<'info, Rent>
+}
+
+#[derive(Accounts)]
+pub struct ReleaseVested<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(mut, seeds=[b"vesting", user.key().as_ref()], bump=vesting_account.bump)]
+    pub vesting_account: Account<'info, VestingAccount>,
+    #[account(seeds=[b"state"], bump)]
+    pub donation_service: Box<Account<'info, DonationService>>,
+    #[account(mut, address=donation_service.token_mint)]
+    pub chrt_mint: Account<'info, Mint>,
+    #[account(init_if_needed, payer=user, associated_token::mint=chrt_mint, associated_token::authority=user)]
+    pub user_chrt_account: Account<'info, TokenAccount>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub rent: Sysvar<'info, Rent>
+}
+
+#[event]
+pub struct PauseStateChanged {
+    pub paused: bool,
+    pub authority: Pubkey,
+}
+
+#[event]
+pub struct OrganizerVerificationChanged {
+    pub organizer: Pubkey,
+    pub verified: bool,
+    pub verified_until: u64,
+    pub authority: Pubkey,
+}
+
+#[event]
+pub struct CreationDepositSettled {
+    pub fundraising_id: u64,
+    pub amount: u64,
+    pub in_chrt: bool,
+    pub is_forfeited: bool,
+}
+
+#[event]
+pub struct CancelProposalOpened {
+    pub fundraising_id: u64,
+    pub proposal_id: u64,
+    pub proposer: Pubkey,
+    pub voting_ends_at: u64,
+    pub grace_ends_at: u64,
+}
+
+#[event]
+pub struct CancelVoteCast {
+    pub fundraising_id: u64,
+    pub proposal_id: u64,
+    pub voter: Pubkey,
+    pub amount: u64,
+    pub is_cancel_vote: bool,
+}
+
+#[event]
+pub struct CancelProposalAppealed {
+    pub fundraising_id: u64,
+    pub proposal_id: u64,
+    pub organizer: Pubkey,
+    pub appeal_stake: u64,
+    pub is_blocked: bool,
+}
+
+#[event]
+pub struct AppealStakeWithdrawn {
+    pub fundraising_id: u64,
+    pub proposal_id: u64,
+    pub organizer: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct DonationRefunded {
+    pub fundraising_id: u64,
+    pub donater: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct SubscriptionCreated {
+    pub fundraising_id: u64,
+    pub donater: Pubkey,
+    pub amount: u64,
+    pub interval_seconds: u64,
+    pub balance: u64,
+}
+
+#[event]
+pub struct SubscriptionExecuted {
+    pub fundraising_id: u64,
+    pub donater: Pubkey,
+    pub amount: u64,
+    pub fee: u64,
+    pub next_payment_at: u64,
+    pub remaining_balance: u64,
+}
+
+#[event]
+pub struct SubscriptionCanceled {
+    pub fundraising_id: u64,
+    pub donater: Pubkey,
+    pub refunded_amount: u64,
+}
+
+#[event]
+pub struct RedistributionClaimed {
+    pub fundraising_id: u64,
+    pub amount: u64,
+}
+
+#[event]
+pub struct FundraisingCanceled {
+    pub fundraising_id: u64,
+    pub proposal_id: u64,
+    pub redistributed_sum: u64,
+}
+
+#[event]
+pub struct FundraisingExpired {
+    pub fundraising_id: u64,
+    pub owner: Pubkey,
+    pub last_activity_at: u64,
+}
+
+#[event]
+pub struct BeneficiaryChangeProposed {
+    pub fundraising_id: u64,
+    pub beneficiary: Pubkey,
+    pub pending_beneficiary: Pubkey,
+}
+
+#[event]
+pub struct BeneficiaryChanged {
+    pub fundraising_id: u64,
+    pub previous_beneficiary: Pubkey,
+    pub beneficiary: Pubkey,
+}
+
+#[event]
+pub struct WithdrawalRequested {
+    pub fundraising_id: u64,
+    pub proposer: Pubkey,
+}
+
+#[event]
+pub struct WithdrawalApproved {
+    pub fundraising_id: u64,
+    pub approver: Pubkey,
+    pub approvals_num: u64,
+    pub threshold: u8,
+}
+
+#[event]
+pub struct FundraisingPauseChanged {
+    pub fundraising_id: u64,
+    pub paused: bool,
+    pub authority: Pubkey,
+}
+
+#[event]
+pub struct CoOrganizerUpdated {
+    pub fundraising_id: u64,
+    pub organizer: Pubkey,
+    pub can_update_metadata: bool,
+    pub can_withdraw: bool,
+    pub can_pause: bool,
+}
+
+#[event]
+pub struct CoOrganizerRemoved {
+    pub fundraising_id: u64,
+    pub organizer: Pubkey,
+}
+
+#[event]
+pub struct FundraisingMetadataUpdated {
+    pub fundraising_id: u64,
+    pub category: FundraisingCategory,
+    pub content_hash: [u8; 32],
+    pub title: String,
+    pub metadata_uri: String,
+}
+
 #[error_code]
 pub enum DonationError {
     #[msg("Only funding owner can call this")]
@@ -222,65 +1189,802 @@ pub enum DonationError {
     #[msg("It's too early")]
     TooEarly,
     #[msg("Invalid token account")]
-    InvalidTokenAccount
+    InvalidTokenAccount,
+    #[msg("No rewards to claim")]
+    NothingToClaim,
+    #[msg("Vesting cliff can't be longer than vesting duration")]
+    InvalidVestingSchedule,
+    #[msg("No vested tokens to release")]
+    NothingToRelease,
+    #[msg("Fundraising is still active")]
+    FundraisingActive,
+    #[msg("Unable to donate 0 chrt tokens")]
+    ZeroChrtAmount,
+    #[msg("Quorum can't exceed 100%")]
+    InvalidQuorum,
+    #[msg("Cancel proposal voting is in progress")]
+    VotingInProgress,
+    #[msg("Cancel proposal voting has ended")]
+    VotingEnded,
+    #[msg("Cancel proposal hasn't passed")]
+    ProposalNotPassed,
+    #[msg("Cancel proposal has been executed")]
+    ProposalExecuted,
+    #[msg("Cancel proposal is in the appeal grace period")]
+    GracePeriod,
+    #[msg("Cancel proposal appeal grace period has ended")]
+    GracePeriodEnded,
+    #[msg("Appeal stake has already been withdrawn")]
+    AppealStakeWithdrawn,
+    #[msg("Nothing to refund")]
+    NothingToRefund,
+    #[msg("Donation has already been refunded")]
+    AlreadyRefunded,
+    #[msg("Fundraising is not in the active fundraisings list")]
+    FundraisingNotActive,
+    #[msg("Account bump not found")]
+    BumpNotFound,
+    #[msg("Math overflow")]
+    MathOverflow,
+    #[msg("Clock sysvar is unavailable")]
+    ClockUnavailable,
+    #[msg("Fundraising status transition is not allowed")]
+    InvalidStatusTransition,
+    #[msg("Fundraising title is too long")]
+    TitleTooLong,
+    #[msg("Metadata URI is too long")]
+    MetadataUriTooLong,
+    #[msg("Account is not the fundraising beneficiary")]
+    InvalidBeneficiary,
+    #[msg("Signer is not the pending beneficiary")]
+    NotPendingBeneficiary,
+    #[msg("Too many co-organizers")]
+    TooManyCoOrganizers,
+    #[msg("Co-organizer not found")]
+    CoOrganizerNotFound,
+    #[msg("Too many withdrawal signers")]
+    TooManyWithdrawalSigners,
+    #[msg("Withdrawal threshold must not exceed the number of unique signers")]
+    InvalidWithdrawalThreshold,
+    #[msg("Signer is not in the withdrawal signer set")]
+    NotWithdrawalSigner,
+    #[msg("Withdrawal is already approved by this signer")]
+    AlreadyApproved,
+    #[msg("Withdrawal requires multisig approval")]
+    WithdrawalApprovalRequired,
+    #[msg("Not enough withdrawal approvals")]
+    NotEnoughApprovals,
+    #[msg("Only owner or guardian can call this")]
+    NotGuardian,
+    #[msg("Donation service is paused")]
+    ServicePaused,
+    #[msg("Fundraising is paused by its organizers")]
+    FundraisingPaused,
+    #[msg("Only owner or verifier can call this")]
+    NotVerifier,
+    #[msg("Organizer is not verified")]
+    OrganizerNotVerified,
+    #[msg("Verification expiry must be in the future")]
+    InvalidVerificationExpiry,
+    #[msg("Fundraising expiry is disabled")]
+    ExpiryDisabled,
+    #[msg("Fundraising is not inactive long enough to expire")]
+    FundraisingNotStale,
+    #[msg("Only fundraisings without funds, stakes or cancel proposals can expire")]
+    FundraisingNotEmpty,
+    #[msg("Subscription interval must be positive")]
+    InvalidSubscriptionInterval,
+    #[msg("Subscription payment is not due yet")]
+    SubscriptionNotDue,
+    #[msg("Subscription balance is too low for the next payment")]
+    InsufficientSubscriptionBalance,
+    #[msg("Withdrawal signers can't be changed once multisig is enabled")]
+    WithdrawalSignersLocked,
+    #[msg("Previous cancel proposal is missing or invalid")]
+    InvalidPreviousProposal,
+    #[msg("Passed cancel proposal must be executed first")]
+    PassedProposalPending,
+    #[msg("No redistributed share to claim")]
+    NothingToRedistribute,
+    #[msg("Every reward slot needs its own pending reward account")]
+    DuplicateRewardAccounts
+
+}
+
+fn current_timestamp() -> Result<u64> {
+    Ok(Clock::get().map_err(|_| DonationError::ClockUnavailable)?.unix_timestamp as u64)
+}
+
+fn move_lamports(from: &AccountInfo, to: &AccountInfo, amount: u64) -> Result<()> {
+    let from_lamports = from.lamports().checked_sub(amount).ok_or(DonationError::MathOverflow)?;
+    let to_lamports = to.lamports().checked_add(amount).ok_or(DonationError::MathOverflow)?;
+
+    **from.try_borrow_mut_lamports()? = from_lamports;
+    **to.try_borrow_mut_lamports()? = to_lamports;
+    Ok(())
+}
+
+// Splits the amount proportionally to the weights without losing the rounding dust
+fn split_by_largest_remainder(amount: u64, weights: &[u64]) -> Vec<u64> {
+    let total_weight: u128 = weights.iter().map(|x| *x as u128).sum();
+
+    let mut shares: Vec<u64> = weights.iter().map(|x| (amount as u128 * *x as u128 / total_weight) as u64).collect();
+    let mut remainders: Vec<(u128, usize)> = weights.iter().enumerate().map(|(i, x)| (amount as u128 * *x as u128 % total_weight, i)).collect();
+    remainders.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+
+    let distributed: u64 = shares.iter().sum();
+    for (_, i) in remainders.iter().take((amount - distributed) as usize) {
+        shares[*i] += 1;
+    }
+    shares
+}
+
+fn set_organizer_verification(organizer_account: &mut Organizer, authority: Pubkey, organizer: Pubkey, verified: bool, verified_until: u64) -> Result<()> {
+    organizer_account.organizer = organizer;
+    organizer_account.verified = verified;
+    organizer_account.verified_until = verified_until;
+    organizer_account.verified_by = authority;
+
+    emit!(OrganizerVerificationChanged {
+        organizer,
+        verified,
+        verified_until,
+        authority
+    });
+    Ok(())
+}
+
+fn set_fundraising_paused(ctx: Context<SetFundraisingPaused>, fundraising_id: u64, paused: bool) -> Result<()> {
+    require!(!ctx.accounts.donation_service.paused, DonationError::ServicePaused);
+
+    let fundraising_account = &mut ctx.accounts.fundraising;
+
+    require!(fundraising_account.is_active(), DonationError::FundraisingFinished);
+
+    fundraising_account.is_paused = paused;
+    // A paused fundraising can't receive donations, so the expiry period restarts from the organizer's decision
+    fundraising_account.last_activity_at = current_timestamp()?;
+
+    emit!(FundraisingPauseChanged {
+        fundraising_id,
+        paused,
+        authority: ctx.accounts.authority.key()
+    });
+    Ok(())
+}
+
+// CHRT deposits are burned since nothing could move them out of the service, SOL deposits go to the platform fee
+fn forfeit_creation_deposit<'info>(
+    fundraising_account: &mut Account<'info, Fundraising>,
+    donation_account: &mut Account<'info, DonationService>,
+    fundraising_token_account: &AccountInfo<'info>,
+    chrt_mint: &Account<'info, Mint>,
+    token_program: &Program<'info, Token>
+) -> Result<()> {
+    let deposit_amount = fundraising_account.deposit_amount;
+    if deposit_amount == 0 {
+        return Ok(());
+    }
+
+    if fundraising_account.deposit_in_chrt {
+        let fundraising_id_bytes = fundraising_account.id.to_le_bytes();
+        let fundraising_bump = fundraising_account.bump.to_le_bytes();
+
+        let inner = vec![
+            b"fundraising".as_ref(),
+            fundraising_id_bytes.as_ref(),
+            fundraising_bump.as_ref()
+        ];
+        let outer = vec![inner.as_slice()];
+
+        let cpi_ctx = CpiContext::new_with_signer(token_program.to_account_info(),
+            anchor_spl::token::Burn{ mint: chrt_mint.to_account_info(), from: fundraising_token_account.clone(), authority: fundraising_account.to_account_info() },
+            outer.as_slice()
+        );
+        anchor_spl::token::burn(cpi_ctx, deposit_amount)?;
+        donation_account.total_burned_forfeited_chrt = donation_account.total_burned_forfeited_chrt.checked_add(deposit_amount).ok_or(DonationError::MathOverflow)?;
+    } else {
+        move_lamports(&fundraising_account.to_account_info(), &donation_account.to_account_info(), deposit_amount)?;
+        donation_account.total_fee = donation_account.total_fee.checked_add(deposit_amount).ok_or(DonationError::MathOverflow)?;
+    }
+
+    fundraising_account.deposit_amount = 0;
+
+    emit!(CreationDepositSettled {
+        fundraising_id: fundraising_account.id,
+        amount: deposit_amount,
+        in_chrt: fundraising_account.deposit_in_chrt,
+        is_forfeited: true
+    });
+    Ok(())
+}
+
+// Shares are only recorded here so the cancellation doesn't depend on the number of active fundraisings,
+// the lamports wait in the service account until each recipient claims its share
+fn redistribute_canceled_balance(
+    fundraising_account: &mut Account<Fundraising>,
+    donation_account: &mut Account<DonationService>,
+    weights: &[u64]
+) -> Result<()> {
+    let shares = split_by_largest_remainder(fundraising_account.total_sum, weights);
+
+    for (active_balance, share) in donation_account.active_fundraising_balances.iter_mut().zip(shares) {
+        active_balance.balance = active_balance.balance.checked_add(share).ok_or(DonationError::MathOverflow)?;
+        active_balance.pending_redistribution = active_balance.pending_redistribution.checked_add(share).ok_or(DonationError::MathOverflow)?;
+    }
+
+    move_lamports(&fundraising_account.to_account_info(), &donation_account.to_account_info(), fundraising_account.total_sum)?;
+
+    fundraising_account.total_sum = 0;
+    Ok(())
+}
+
+// Moves a redistributed share out of the service account into the fundraising
+fn settle_redistribution(fundraising_account: &mut Account<Fundraising>, donation_account: &Account<DonationService>, amount: u64) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+
+    move_lamports(&donation_account.to_account_info(), &fundraising_account.to_account_info(), amount)?;
+
+    fundraising_account.total_sum = fundraising_account.total_sum.checked_add(amount).ok_or(DonationError::MathOverflow)?;
+    Ok(())
+}
+
+// The latest proposal is passed in the remaining accounts once any was opened, its pubkey is derived from the proposals counter
+fn require_no_passed_proposal(fundraising_account: &Fundraising, latest_proposal_info: Option<&AccountInfo>, quorum_bps: u64, program_id: &Pubkey) -> Result<()> {
+    if fundraising_account.cancel_proposals_num == 0 {
+        return Ok(());
+    }
+
+    let latest_proposal_info = latest_proposal_info.ok_or(DonationError::InvalidPreviousProposal)?;
+    require!(latest_proposal_info.owner == program_id, DonationError::InvalidPreviousProposal);
+
+    let latest_proposal = CancelProposal::try_deserialize(&mut &latest_proposal_info.try_borrow_data()?[..])?;
+    require!(latest_proposal.fundraising_id == fundraising_account.id && latest_proposal.proposal_id == fundraising_account.cancel_proposals_num - 1, DonationError::InvalidPreviousProposal);
+    require!(!latest_proposal.is_passed(quorum_bps), DonationError::PassedProposalPending);
+    Ok(())
+}
+
+// Remaining accounts are the latest cancel proposal if any was opened, followed by the fundraising and the owner ATAs to return a CHRT deposit
+fn withdraw_fundraising<'info>(accounts: &mut Withdraw<'info>, remaining_accounts: &[AccountInfo<'info>], fundraising_id: u64, program_id: &Pubkey) -> Result<()> {
+    let fundraising_account = &mut accounts.fundraising;
+    let beneficiary_account = &accounts.beneficiary;
+    let donation_account = &mut accounts.donation_service;
+
+    require!(fundraising_account.is_active(), DonationError::FundraisingFinished);
+
+    // Otherwise the organizer could empty the fundraising as soon as the vote turns against it
+    require!(fundraising_account.cancel_voting_ends_at <= current_timestamp()?, DonationError::VotingInProgress);
+    require_no_passed_proposal(fundraising_account, remaining_accounts.get(0), donation_account.cancel_quorum_bps, program_id)?;
+
+    let token_accounts = if fundraising_account.cancel_proposals_num > 0 {&remaining_accounts[1..]} else {remaining_accounts};
+
+    let active_donation_balance_id = donation_account.active_fundraising_balances.binary_search_by(|x|x.id.cmp(&fundraising_id)).map_err(|_| DonationError::FundraisingNotActive)?;
+    let active_balance = donation_account.active_fundraising_balances.remove(active_donation_balance_id);
+    settle_redistribution(fundraising_account, donation_account, active_balance.pending_redistribution)?;
+
+    move_lamports(&fundraising_account.to_account_info(), &beneficiary_account.to_account_info(), fundraising_account.total_sum)?;
+
+    let deposit_amount = fundraising_account.deposit_amount;
+    if deposit_amount > 0 {
+        if fundraising_account.deposit_in_chrt {
+            let fundraising_token_account = token_accounts.get(0).ok_or(DonationError::InvalidTokenAccount)?;
+            let owner_token_account = token_accounts.get(1).ok_or(DonationError::InvalidTokenAccount)?;
+            require!(fundraising_token_account.key() == get_associated_token_address(&fundraising_account.key(), &donation_account.token_mint), DonationError::InvalidTokenAccount);
+            require!(owner_token_account.key() == get_associated_token_address(&fundraising_account.owner, &donation_account.token_mint), DonationError::InvalidTokenAccount);
+
+            let fundraising_id_bytes = fundraising_id.to_le_bytes();
+            let fundraising_bump = fundraising_account.bump.to_le_bytes();
+
+            let inner = vec![
+                b"fundraising".as_ref(),
+                fundraising_id_bytes.as_ref(),
+                fundraising_bump.as_ref()
+            ];
+            let outer = vec![inner.as_slice()];
+
+            let cpi_ctx = CpiContext::new_with_signer(accounts.token_program.to_account_info(),
+                anchor_spl::token::Transfer{ from: fundraising_token_account.clone(), to: owner_token_account.clone(), authority: fundraising_account.to_account_info() },
+                outer.as_slice()
+            );
+            anchor_spl::token::transfer(cpi_ctx, deposit_amount)?;
+        } else {
+            move_lamports(&fundraising_account.to_account_info(), &accounts.owner.to_account_info(), deposit_amount)?;
+        }
+
+        fundraising_account.deposit_amount = 0;
+
+        emit!(CreationDepositSettled {
+            fundraising_id,
+            amount: deposit_amount,
+            in_chrt: fundraising_account.deposit_in_chrt,
+            is_forfeited: false
+        });
+    }
+
+    fundraising_account.transition_to(FundraisingStatus::Withdrawn)?;
+    fundraising_account.total_sum = 0;
+    Ok(())
+}
+
+// Fee and leaderboard bookkeeping shared by donate and execute_subscription, returns the platform fee
+fn record_donation(
+    donation_account: &mut DonationService,
+    fundraising_account: &mut Fundraising,
+    donater_info_account: &mut DonaterInfo,
+    donater_top_info_account: &mut GlobalTopInfo,
+    fundraising_id: u64,
+    amount: u64
+) -> Result<u64> {
+    let is_fee_disabled = fundraising_account.total_no_fee_chrt_sum < donation_account.no_fee_chrt_threshold;
+    let potential_fee = (amount / 100).checked_mul(donation_account.owner_fee_percent).ok_or(DonationError::MathOverflow)?;
+    let fee: u64 = if is_fee_disabled {potential_fee} else {0};
+    let sum_to_donate = amount.checked_sub(fee).ok_or(DonationError::MathOverflow)?;
+
+    if is_fee_disabled {
+        donation_account.total_dropped_fee = donation_account.total_dropped_fee.checked_add(potential_fee).ok_or(DonationError::MathOverflow)?;
+    }
+
+    fundraising_account.total_sum = fundraising_account.total_sum.checked_add(sum_to_donate).ok_or(DonationError::MathOverflow)?;
+    fundraising_account.total_donated = fundraising_account.total_donated.checked_add(amount).ok_or(DonationError::MathOverflow)?;
+    fundraising_account.last_activity_at = current_timestamp()?;
+    donation_account.total_fee = donation_account.total_fee.checked_add(fee).ok_or(DonationError::MathOverflow)?;
+    donater_info_account.total_sum = donater_info_account.total_sum.checked_add(amount).ok_or(DonationError::MathOverflow)?;
+    donater_top_info_account.nominated_sum = donater_top_info_account.nominated_sum.checked_add(amount).ok_or(DonationError::MathOverflow)?;
+    donation_account.total_donations_sum = donation_account.total_donations_sum.checked_add(amount).ok_or(DonationError::MathOverflow)?;
+
+    let active_donation_balance_id = donation_account.active_fundraising_balances.binary_search_by(|x|x.id.cmp(&fundraising_id)).map_err(|_| DonationError::FundraisingNotActive)?;
+    donation_account.active_fundraising_balances[active_donation_balance_id].balance = donation_account.active_fundraising_balances[active_donation_balance_id].balance.checked_add(amount).ok_or(DonationError::MathOverflow)?;
+
+    if donater_info_account.total_sum > fundraising_account.top_donaters[2].map_or(0, |x| x.total_sum){
+        let top_donater_position = fundraising_account.top_donaters.iter()
+        .position(|x|x.map_or(false, |v| v.donater == donater_info_account.donater));
+
+        let mut top_donaters = fundraising_account.top_donaters.to_vec();
+
+        if let Some(top_donater_position) = top_donater_position {
+            top_donaters[top_donater_position] = Some(DonaterTopInfo{ total_sum: donater_info_account.total_sum, donater: donater_info_account.donater });
+        } else {
+            top_donaters.push(Some(DonaterTopInfo{ total_sum: donater_info_account.total_sum, donater: donater_info_account.donater }));
+        }
+
+        top_donaters.sort_by(|b, a|{
+            let a_sum = a.map_or(0, |x|x.total_sum);
+            let b_sum = b.map_or(0, |x|x.total_sum);
+            a_sum.cmp(&b_sum)
+        });
+        for i in 0..3 {
+            fundraising_account.top_donaters[i] = top_donaters[i];
+        }
+    }
+
+    if donater_info_account.total_sum > donation_account.top_donaters[9].map_or(0, |x| x.total_sum) {
+        let top_donater_position = donation_account.top_donaters.iter()
+            .position(|x|x.map_or(false, |v| v.donater == donater_info_account.donater));
+
+        let mut top_donaters = donation_account.top_donaters.to_vec();
+
+        if let Some(top_donater) = top_donater_position.and_then(|i| top_donaters[i].as_mut()) {
+            top_donater.total_sum = top_donater.total_sum.checked_add(amount).ok_or(DonationError::MathOverflow)?;
+        } else {
+            top_donaters.push(Some(DonaterTopInfo{ total_sum: donater_info_account.total_sum, donater: donater_info_account.donater }));
+        }
+
+        top_donaters.sort_by(|b, a|{
+            let a_sum = a.map_or(0, |x|x.total_sum);
+            let b_sum = b.map_or(0, |x|x.total_sum);
+            a_sum.cmp(&b_sum)
+        });
+        for i in 0..10 {
+            donation_account.top_donaters[i] = top_donaters[i];
+        }
+    }
+
+    if donater_top_info_account.nominated_sum > donation_account.nominated_donaters[9].map_or(0, |x| x.total_sum) {
+        let top_donater_position = donation_account.nominated_donaters.iter()
+            .position(|x|x.map_or(false, |v| v.donater == donater_info_account.donater));
+        
+        let mut nominated_donaters = donation_account.nominated_donaters.to_vec();
+
+        if let Some(top_donater_position) = top_donater_position {
+            nominated_donaters[top_donater_position] = Some(DonaterTopInfo{ total_sum: donater_top_info_account.nominated_sum, donater: donater_info_account.donater });
+        } else {
+            nominated_donaters.push(Some(DonaterTopInfo{ total_sum: donater_top_info_account.nominated_sum, donater: donater_info_account.donater }));
+        }
+
+        nominated_donaters.sort_by(|b, a|{
+            let a_sum = a.map_or(0, |x|x.total_sum);
+            let b_sum = b.map_or(0, |x|x.total_sum);
+            a_sum.cmp(&b_sum)
+        });
+        for i in 0..10 {
+            donation_account.nominated_donaters[i] = nominated_donaters[i];
+        }
+    }
+
+    Ok(fee)
+}
+
+// Referral CHRT is vested when a vesting schedule is configured, otherwise minted right away
+fn reward_referrer<'info>(
+    donation_account: &Account<'info, DonationService>,
+    referrer_vesting_account: &mut Account<'info, VestingAccount>,
+    referrer_chrt_account: &Account<'info, TokenAccount>,
+    chrt_mint: &Account<'info, Mint>,
+    token_program: &Program<'info, Token>,
+    amount: u64
+) -> Result<()> {
+    let referrer_reward = amount.checked_mul(101).ok_or(DonationError::MathOverflow)?;
+
+    if donation_account.vesting_duration_seconds > 0 {
+        let current_time = current_timestamp()?;
+        referrer_vesting_account.credit(referrer_reward, donation_account.vesting_cliff_seconds, donation_account.vesting_duration_seconds, current_time)?;
+        return Ok(());
+    }
+
+    let state_bump = donation_account.bump.to_le_bytes();
+
+    let inner = vec![
+        b"state".as_ref(),
+        state_bump.as_ref()
+    ];
+    let outer = vec![inner.as_slice()];
+
+    let cpi_ctx = CpiContext::new_with_signer(token_program.to_account_info(),
+    anchor_spl::token::MintTo{
+        to: referrer_chrt_account.to_account_info(),
+        mint: chrt_mint.to_account_info(),
+        authority: donation_account.to_account_info(),
+    }, outer.as_slice());
+    anchor_spl::token::mint_to(cpi_ctx, referrer_reward)
+}
+
+fn vote_on_cancel_proposal(ctx: Context<VoteCancelProposal>, amount: u64, is_cancel_vote: bool) -> Result<()> {
+    require!(!ctx.accounts.donation_service.paused, DonationError::ServicePaused);
+
+    let fundraising_account = &ctx.accounts.fundraising;
+    let cancel_proposal_account = &mut ctx.accounts.cancel_proposal;
+    let cancel_vote_account = &mut ctx.accounts.cancel_vote;
+
+    require!(amount > 0, DonationError::ZeroChrtAmount);
+    require!(fundraising_account.is_active(), DonationError::FundraisingFinished);
+
+    let current_time = current_timestamp()?;
+    require!(current_time < cancel_proposal_account.voting_ends_at, DonationError::VotingEnded);
+
+    let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), 
+        anchor_spl::token::Transfer{ from: ctx.accounts.voter_token_account.to_account_info(), to: ctx.accounts.proposal_token_account.to_account_info(), authority: ctx.accounts.voter.to_account_info() }
+    );
+    anchor_spl::token::transfer(cpi_ctx, amount)?;
+
+    cancel_vote_account.voter = ctx.accounts.voter.key();
+    cancel_vote_account.bump = *ctx.bumps.get("cancel_vote").ok_or(DonationError::BumpNotFound)?;
+
+    if is_cancel_vote {
+        cancel_vote_account.cancel_amount = cancel_vote_account.cancel_amount.checked_add(amount).ok_or(DonationError::MathOverflow)?;
+        cancel_proposal_account.cancel_votes = cancel_proposal_account.cancel_votes.checked_add(amount).ok_or(DonationError::MathOverflow)?;
+    } else {
+        cancel_vote_account.keep_amount = cancel_vote_account.keep_amount.checked_add(amount).ok_or(DonationError::MathOverflow)?;
+        cancel_proposal_account.keep_votes = cancel_proposal_account.keep_votes.checked_add(amount).ok_or(DonationError::MathOverflow)?;
+    }
+
+    emit!(CancelVoteCast {
+        fundraising_id: cancel_proposal_account.fundraising_id,
+        proposal_id: cancel_proposal_account.proposal_id,
+        voter: cancel_vote_account.voter,
+        amount,
+        is_cancel_vote
+    });
+    Ok(())
+}
+
+#[program]
+pub mod solana_donation {
+
+    use anchor_lang::{solana_program::{system_instruction, program::invoke}};
+    use anchor_spl::token::{MintTo, self, Transfer, Burn, CloseAccount};
+
+    use super::*;
+
+    pub fn initialize(ctx: Context<Initialize>, reward_period_seconds: u64, owner_fee_percent: u64, reward_chrt_amount: u64, no_fee_chrt_threshold: u64, cancel_quorum_bps: u64, cancel_voting_period_seconds: u64, cancel_grace_period_seconds: u64, redistribution_policy: RedistributionPolicy, reserve_fund: Pubkey, token_mint: Pubkey, permissionless_rewards: bool, crank_tip_chrt_amount: u64, vesting_cliff_seconds: u64, vesting_duration_seconds: u64) -> Result<()> {
+        require!(vesting_cliff_seconds <= vesting_duration_seconds, DonationError::InvalidVestingSchedule);
+        require!(cancel_quorum_bps <= 10_000, DonationError::InvalidQuorum);
+
+        let donation_service_account = &mut ctx.accounts.donation_service;
+        donation_service_account.reward_period_seconds = reward_period_seconds;
+        donation_service_account.owner_fee_percent = owner_fee_percent;
+        donation_service_account.reward_chrt_amount = reward_chrt_amount;
+        donation_service_account.no_fee_chrt_threshold = no_fee_chrt_threshold;
+        donation_service_account.cancel_quorum_bps = cancel_quorum_bps;
+        donation_service_account.cancel_voting_period_seconds = cancel_voting_period_seconds;
+        donation_service_account.cancel_grace_period_seconds = cancel_grace_period_seconds;
+        donation_service_account.redistribution_policy = redistribution_policy;
+        donation_service_account.reserve_fund = reserve_fund;
+        donation_service_account.owner = ctx.accounts.owner.key();
+        donation_service_account.guardian = ctx.accounts.owner.key();
+        donation_service_account.verifier = ctx.accounts.owner.key();
+        donation_service_account.bump = *ctx.bumps.get("donation_service").ok_or(DonationError::BumpNotFound)?;
+        donation_service_account.token_mint = token_mint;
+        donation_service_account.permissionless_rewards = permissionless_rewards;
+        donation_service_account.crank_tip_chrt_amount = crank_tip_chrt_amount;
+        donation_service_account.vesting_cliff_seconds = vesting_cliff_seconds;
+        donation_service_account.vesting_duration_seconds = vesting_duration_seconds;
+
+        Ok(())
+    }
+
+    pub fn set_guardian(ctx: Context<SetGuardian>, guardian: Pubkey) -> Result<()> {
+        ctx.accounts.donation_service.guardian = guardian;
+        Ok(())
+    }
+
+    // Guardian can only stop the platform, resuming it is up to the owner.
+    // While paused only exits stay open: withdrawals, refunds, unstaking and claims of already earned CHRT
+    pub fn pause(ctx: Context<SetPaused>) -> Result<()> {
+        let donation_account = &mut ctx.accounts.donation_service;
+        let authority = ctx.accounts.authority.key();
+
+        require!(authority == donation_account.owner || authority == donation_account.guardian, DonationError::NotGuardian);
+
+        donation_account.paused = true;
+
+        emit!(PauseStateChanged {
+            paused: true,
+            authority
+        });
+        Ok(())
+    }
+
+    pub fn unpause(ctx: Context<SetPaused>) -> Result<()> {
+        let donation_account = &mut ctx.accounts.donation_service;
+        let authority = ctx.accounts.authority.key();
+
+        require!(authority == donation_account.owner, DonationError::NotOwner);
+
+        donation_account.paused = false;
+
+        emit!(PauseStateChanged {
+            paused: false,
+            authority
+        });
+        Ok(())
+    }
+
+    pub fn configure_verification(ctx: Context<ConfigureVerification>, verifier: Pubkey, verified_organizers_only: bool) -> Result<()> {
+        let donation_account = &mut ctx.accounts.donation_service;
+        donation_account.verifier = verifier;
+        donation_account.verified_organizers_only = verified_organizers_only;
+        Ok(())
+    }
+
+    pub fn verify_organizer(ctx: Context<VerifyOrganizer>, organizer: Pubkey, verified_until: u64) -> Result<()> {
+        require!(verified_until > current_timestamp()?, DonationError::InvalidVerificationExpiry);
+
+        let organizer_account = &mut ctx.accounts.organizer_account;
+        organizer_account.bump = *ctx.bumps.get("organizer_account").ok_or(DonationError::BumpNotFound)?;
+
+        set_organizer_verification(organizer_account, ctx.accounts.authority.key(), organizer, true, verified_until)
+    }
+
+    pub fn revoke_organizer(ctx: Context<RevokeOrganizer>, organizer: Pubkey) -> Result<()> {
+        set_organizer_verification(&mut ctx.accounts.organizer_account, ctx.accounts.authority.key(), organizer, false, 0)
+    }
+
+    pub fn configure_creation_deposit(ctx: Context<ConfigureCreationDeposit>, amount: u64, in_chrt: bool) -> Result<()> {
+        let donation_account = &mut ctx.accounts.donation_service;
+        donation_account.creation_deposit_amount = amount;
+        donation_account.creation_deposit_in_chrt = in_chrt;
+        Ok(())
+    }
+
+    pub fn configure_expiry(ctx: Context<ConfigureExpiry>, expiry_period_seconds: u64) -> Result<()> {
+        ctx.accounts.donation_service.expiry_period_seconds = expiry_period_seconds;
+        Ok(())
+    }
+
+    // Remaining accounts are the owner token account and the fundraising ATA when the deposit is taken in CHRT
+    pub fn create_fundraising<'info>(ctx: Context<'_, '_, '_, 'info, CreateFundraising<'info>>, title: String, metadata_uri: String, content_hash: [u8; 32], category: FundraisingCategory, beneficiary: Pubkey) -> Result<()> {
+        require!(!ctx.accounts.donation_service.paused, DonationError::ServicePaused);
+
+        let donation_service_account = &mut ctx.accounts.donation_service;
+
+        require!(title.len() <= MAX_TITLE_LEN, DonationError::TitleTooLong);
+        require!(metadata_uri.len() <= MAX_METADATA_URI_LEN, DonationError::MetadataUriTooLong);
+
+        require!(donation_service_account.active_fundraising_balances.len() < ACTIVE_FUNDRAISINGS_LIMIT, DonationError::ActiveFundraisingsLimitExceeded);
+
+        let organizer_info = ctx.accounts.organizer.to_account_info();
+        let verified = if organizer_info.owner == ctx.program_id && !organizer_info.data_is_empty() {
+            let organizer_account = Organizer::try_deserialize(&mut &organizer_info.try_borrow_data()?[..])?;
+            organizer_account.is_verified(current_timestamp()?)
+        } else {
+            false
+        };
+        require!(verified || !donation_service_account.verified_organizers_only, DonationError::OrganizerNotVerified);
+
+        let new_fundraising_id = donation_service_account.fundraisings_num;
+        donation_service_account.fundraisings_num = donation_service_account.fundraisings_num.checked_add(1).ok_or(DonationError::MathOverflow)?;
+        donation_service_account.active_fundraising_balances.push(ActiveFundraisingBalance { id: new_fundraising_id, balance: 0, pending_redistribution: 0 });
+
+        let fundraising_account = &mut ctx.accounts.fundraising;
+        fundraising_account.bump = *ctx.bumps.get("fundraising").ok_or(DonationError::BumpNotFound)?;
+        fundraising_account.id = new_fundraising_id;
+        fundraising_account.owner = ctx.accounts.owner.key();
+        fundraising_account.verified = verified;
+        fundraising_account.beneficiary = beneficiary;
+        fundraising_account.status = FundraisingStatus::Active;
+        fundraising_account.last_activity_at = current_timestamp()?;
+        fundraising_account.cancel_grace_period_seconds = donation_service_account.cancel_grace_period_seconds;
+        fundraising_account.category = category;
+        fundraising_account.content_hash = content_hash;
+        fundraising_account.title = title;
+        fundraising_account.metadata_uri = metadata_uri;
+
+        let deposit_amount = donation_service_account.creation_deposit_amount;
+        if deposit_amount > 0 {
+            if donation_service_account.creation_deposit_in_chrt {
+                let owner_token_account = ctx.remaining_accounts.get(0).ok_or(DonationError::InvalidTokenAccount)?;
+                let fundraising_token_account = ctx.remaining_accounts.get(1).ok_or(DonationError::InvalidTokenAccount)?;
+                // Withdrawal returns the deposit between the same ATAs, the token program checks the mint
+                require!(owner_token_account.key() == get_associated_token_address(&ctx.accounts.owner.key(), &donation_service_account.token_mint), DonationError::InvalidTokenAccount);
+                require!(fundraising_token_account.key() == get_associated_token_address(&fundraising_account.key(), &donation_service_account.token_mint), DonationError::InvalidTokenAccount);
+
+                let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(),
+                    Transfer{ from: owner_token_account.clone(), to: fundraising_token_account.clone(), authority: ctx.accounts.owner.to_account_info() }
+                );
+                token::transfer(cpi_ctx, deposit_amount)?;
+            } else {
+                let deposit_transfer_instruction = system_instruction::transfer(&ctx.accounts.owner.key(), &fundraising_account.key(), deposit_amount);
+
+                invoke(&deposit_transfer_instruction, &[
+                    ctx.accounts.owner.to_account_info(),
+                    fundraising_account.to_account_info()
+                ])?;
+            }
+        }
+        fundraising_account.deposit_amount = deposit_amount;
+        fundraising_account.deposit_in_chrt = donation_service_account.creation_deposit_in_chrt;
+
+        Ok(())
+    }
+
+    pub fn update_fundraising_metadata(ctx: Context<UpdateFundraisingMetadata>, fundraising_id: u64, title: String, metadata_uri: String, content_hash: [u8; 32], category: FundraisingCategory) -> Result<()> {
+        require!(!ctx.accounts.donation_service.paused, DonationError::ServicePaused);
+
+        let fundraising_account = &mut ctx.accounts.fundraising;
+
+        require!(fundraising_account.is_active(), DonationError::FundraisingFinished);
+        require!(title.len() <= MAX_TITLE_LEN, DonationError::TitleTooLong);
+        require!(metadata_uri.len() <= MAX_METADATA_URI_LEN, DonationError::MetadataUriTooLong);
+
+        fundraising_account.category = category;
+        fundraising_account.content_hash = content_hash;
+        fundraising_account.title = title;
+        fundraising_account.metadata_uri = metadata_uri;
+
+        emit!(FundraisingMetadataUpdated {
+            fundraising_id,
+            category,
+            content_hash,
+            title: fundraising_account.title.clone(),
+            metadata_uri: fundraising_account.metadata_uri.clone()
+        });
+        Ok(())
+    }
+
+    pub fn pause_fundraising(ctx: Context<SetFundraisingPaused>, fundraising_id: u64) -> Result<()> {
+        set_fundraising_paused(ctx, fundraising_id, true)
+    }
+
+    pub fn resume_fundraising(ctx: Context<SetFundraisingPaused>, fundraising_id: u64) -> Result<()> {
+        set_fundraising_paused(ctx, fundraising_id, false)
+    }
+
+    pub fn set_co_organizer(ctx: Context<ManageCoOrganizers>, fundraising_id: u64, organizer: Pubkey, can_update_metadata: bool, can_withdraw: bool, can_pause: bool) -> Result<()> {
+        require!(!ctx.accounts.donation_service.paused, DonationError::ServicePaused);
+
+        let fundraising_account = &mut ctx.accounts.fundraising;
+
+        require!(fundraising_account.is_active(), DonationError::FundraisingFinished);
+
+        let co_organizer = CoOrganizer { organizer, can_update_metadata, can_withdraw, can_pause };
+        let co_organizer_position = fundraising_account.co_organizers.iter().position(|x| x.organizer == organizer);
+
+        if let Some(co_organizer_position) = co_organizer_position {
+            fundraising_account.co_organizers[co_organizer_position] = co_organizer;
+        } else {
+            require!(fundraising_account.co_organizers.len() < MAX_CO_ORGANIZERS, DonationError::TooManyCoOrganizers);
+            fundraising_account.co_organizers.push(co_organizer);
+        }
+
+        emit!(CoOrganizerUpdated {
+            fundraising_id,
+            organizer,
+            can_update_metadata,
+            can_withdraw,
+            can_pause
+        });
+        Ok(())
+    }
+
+    pub fn remove_co_organizer(ctx: Context<ManageCoOrganizers>, fundraising_id: u64, organizer: Pubkey) -> Result<()> {
+        require!(!ctx.accounts.donation_service.paused, DonationError::ServicePaused);
+
+        let fundraising_account = &mut ctx.accounts.fundraising;
+
+        let co_organizer_position = fundraising_account.co_organizers.iter().position(|x| x.organizer == organizer)
+            .ok_or(DonationError::CoOrganizerNotFound)?;
+        fundraising_account.co_organizers.remove(co_organizer_position);
 
-}
+        emit!(CoOrganizerRemoved {
+            fundraising_id,
+            organizer
+        });
+        Ok(())
+    }
 
-#[program]
-pub mod solana_donation {
+    pub fn propose_beneficiary(ctx: Context<ProposeBeneficiary>, fundraising_id: u64, new_beneficiary: Pubkey) -> Result<()> {
+        require!(!ctx.accounts.donation_service.paused, DonationError::ServicePaused);
 
-    use anchor_lang::{solana_program::{system_instruction, program::invoke}};
-    use anchor_spl::token::{MintTo, self, Transfer};
+        let fundraising_account = &mut ctx.accounts.fundraising;
 
-    use super::*;
+        require!(fundraising_account.is_active(), DonationError::FundraisingFinished);
 
-    pub fn initialize(ctx: Context<Initialize>, reward_period_seconds: u64, owner_fee_percent: u64, reward_chrt_amount: u64, no_fee_chrt_threshold: u64, cancel_chrt_threshold: u64, token_mint: Pubkey) -> Result<()> {
-        let donation_service_account = &mut ctx.accounts.donation_service;
-        donation_service_account.reward_period_seconds = reward_period_seconds;
-        donation_service_account.owner_fee_percent = owner_fee_percent;
-        donation_service_account.reward_chrt_amount = reward_chrt_amount;
-        donation_service_account.no_fee_chrt_threshold = no_fee_chrt_threshold;
-        donation_service_account.cancel_chrt_threshold = cancel_chrt_threshold;
-        donation_service_account.owner = ctx.accounts.owner.key();
-        donation_service_account.bump = *ctx.bumps.get("donation_service").unwrap();
-        donation_service_account.token_mint = token_mint;
+        fundraising_account.pending_beneficiary = Some(new_beneficiary);
 
+        emit!(BeneficiaryChangeProposed {
+            fundraising_id,
+            beneficiary: fundraising_account.beneficiary,
+            pending_beneficiary: new_beneficiary
+        });
         Ok(())
     }
 
-    pub fn create_fundraising(ctx: Context<CreateFundraising>) -> Result<()> {
-        let donation_service_account = &mut ctx.accounts.donation_service;
+    pub fn accept_beneficiary(ctx: Context<AcceptBeneficiary>, fundraising_id: u64) -> Result<()> {
+        require!(!ctx.accounts.donation_service.paused, DonationError::ServicePaused);
 
-        require!(donation_service_account.active_fundraising_balances.len() < ACTIVE_FUNDRAISINGS_LIMIT, DonationError::ActiveFundraisingsLimitExceeded);
+        let fundraising_account = &mut ctx.accounts.fundraising;
 
-        let new_fundraising_id = donation_service_account.fundraisings_num;
-        donation_service_account.fundraisings_num += 1;
-        donation_service_account.active_fundraising_balances.push(ActiveFundraisingBalance { id: new_fundraising_id, balance: 0 });
+        require!(fundraising_account.is_active(), DonationError::FundraisingFinished);
 
-        let fundraising_account = &mut ctx.accounts.fundraising;
-        fundraising_account.bump = *ctx.bumps.get("fundraising").unwrap();
-        fundraising_account.id = new_fundraising_id;
-        fundraising_account.owner = ctx.accounts.owner.key();
+        let previous_beneficiary = fundraising_account.beneficiary;
+        fundraising_account.beneficiary = ctx.accounts.new_beneficiary.key();
+        fundraising_account.pending_beneficiary = None;
 
+        emit!(BeneficiaryChanged {
+            fundraising_id,
+            previous_beneficiary,
+            beneficiary: fundraising_account.beneficiary
+        });
         Ok(())
     }
 
     pub fn donate(ctx: Context<Donate>, amount: u64, fundraising_id: u64) -> Result<()> {
+        require!(!ctx.accounts.donation_service.paused, DonationError::ServicePaused);
+
         require!(amount > 0, DonationError::ZeroDonation);
 
         let fundraising_account = &mut ctx.accounts.fundraising;
 
-        require!(!fundraising_account.is_finished, DonationError::FundraisingFinished);
+        require!(fundraising_account.is_active(), DonationError::FundraisingFinished);
+        require!(!fundraising_account.is_paused, DonationError::FundraisingPaused);
 
         let donation_account = &mut ctx.accounts.donation_service;
         let donater_account = &mut ctx.accounts.donater;
         let donater_info_account = &mut ctx.accounts.donater_info;
         let donater_top_info_account = &mut ctx.accounts.donater_top_info;
 
-        let is_fee_disabled = fundraising_account.total_no_fee_chrt_sum < donation_account.no_fee_chrt_threshold;
-        let potential_fee = amount / 100 * donation_account.owner_fee_percent;
-        let fee: u64 = if is_fee_disabled {potential_fee} else {0};
-        let sum_to_donate = amount - fee;
+        donater_info_account.donater = donater_account.key();
+        donater_info_account.bump = *ctx.bumps.get("donater_info").ok_or(DonationError::BumpNotFound)?;
+        donater_top_info_account.donater = donater_account.key();
+
+        let donater_pending_reward_account = &mut ctx.accounts.donater_pending_reward;
+        donater_pending_reward_account.owner = donater_account.key();
+        donater_pending_reward_account.bump = *ctx.bumps.get("donater_pending_reward").ok_or(DonationError::BumpNotFound)?;
+
+        let fee = record_donation(donation_account, fundraising_account, donater_info_account, donater_top_info_account, fundraising_id, amount)?;
+        let sum_to_donate = amount.checked_sub(fee).ok_or(DonationError::MathOverflow)?;
 
         let donation_transfer_instruction = system_instruction::transfer(&donater_account.key(), &fundraising_account.key(), sum_to_donate);
 
@@ -298,226 +2002,764 @@ pub mod solana_donation {
             ])?;    
         }
 
-        if is_fee_disabled {
-            donation_account.total_dropped_fee += potential_fee;
-        }
+        let referrer_vesting_account = &mut ctx.accounts.referrer_vesting;
+        referrer_vesting_account.owner = ctx.accounts.referrer_chrt_account.owner;
+        referrer_vesting_account.bump = *ctx.bumps.get("referrer_vesting").ok_or(DonationError::BumpNotFound)?;
+
+        reward_referrer(&ctx.accounts.donation_service, referrer_vesting_account, &ctx.accounts.referrer_chrt_account, &ctx.accounts.chrt_mint, &ctx.accounts.token_program, amount)
+    }
+
+    pub fn donate_chrt(ctx: Context<DonateCHRT>, amount: u64, _fundraising_id: u64) -> Result<()>{
+        require!(!ctx.accounts.donation_service.paused, DonationError::ServicePaused);
+
+        let fundraising_account = &mut ctx.accounts.fundraising;
+        let donater_account = &mut ctx.accounts.donater;
+        let donater_token_account = &mut ctx.accounts.donater_token_account;
+        let donation_account = &mut ctx.accounts.donation_service;
+        require!(amount > 0, DonationError::ZeroChrtAmount);
+        require!(fundraising_account.is_active(), DonationError::FundraisingFinished);
+        require!(!fundraising_account.is_paused, DonationError::FundraisingPaused);
+        require!(donater_token_account.mint == donation_account.token_mint, DonationError::InvalidTokenAccount);
+
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), 
+            Burn{ mint: ctx.accounts.chrt_mint.to_account_info(), from: donater_token_account.to_account_info(), authority: donater_account.to_account_info() }
+        );
+        token::burn(cpi_ctx, amount)?;
+        fundraising_account.total_no_fee_chrt_sum = fundraising_account.total_no_fee_chrt_sum.checked_add(amount).ok_or(DonationError::MathOverflow)?;
+        fundraising_account.last_activity_at = current_timestamp()?;
+        donation_account.total_burned_no_fee_chrt = donation_account.total_burned_no_fee_chrt.checked_add(amount).ok_or(DonationError::MathOverflow)?;
+        Ok(())
+    }
+
+    pub fn create_subscription(ctx: Context<CreateSubscription>, fundraising_id: u64, amount: u64, interval_seconds: u64, deposit: u64) -> Result<()> {
+        require!(!ctx.accounts.donation_service.paused, DonationError::ServicePaused);
+        require!(amount > 0, DonationError::ZeroDonation);
+        require!(interval_seconds > 0, DonationError::InvalidSubscriptionInterval);
+        require!(deposit >= amount, DonationError::InsufficientSubscriptionBalance);
+        require!(ctx.accounts.fundraising.is_active(), DonationError::FundraisingFinished);
+
+        let donater_account = &ctx.accounts.donater;
+        let subscription_account = &mut ctx.accounts.subscription;
+
+        let deposit_transfer_instruction = system_instruction::transfer(&donater_account.key(), &subscription_account.key(), deposit);
+
+        invoke(&deposit_transfer_instruction, &[
+            donater_account.to_account_info(),
+            subscription_account.to_account_info()
+        ])?;
+
+        subscription_account.donater = donater_account.key();
+        subscription_account.fundraising_id = fundraising_id;
+        subscription_account.referrer = ctx.accounts.referrer_chrt_account.owner;
+        subscription_account.amount = amount;
+        subscription_account.interval_seconds = interval_seconds;
+        subscription_account.next_payment_at = current_timestamp()?;
+        subscription_account.balance = deposit;
+        subscription_account.bump = *ctx.bumps.get("subscription").ok_or(DonationError::BumpNotFound)?;
 
-        fundraising_account.total_sum += sum_to_donate;
-        donation_account.total_fee += fee;
-        donater_info_account.total_sum += amount;
-        donater_top_info_account.nominated_sum += amount;
+        // Accounts touched by every payment are created here so the crank never pays rent on the donater's behalf
+        let donater_info_account = &mut ctx.accounts.donater_info;
         donater_info_account.donater = donater_account.key();
-        donation_account.total_donations_sum += amount;
+        donater_info_account.bump = *ctx.bumps.get("donater_info").ok_or(DonationError::BumpNotFound)?;
 
-        let active_donation_balance_id = donation_account.active_fundraising_balances.binary_search_by(|x|x.id.cmp(&fundraising_id)).unwrap();
-        donation_account.active_fundraising_balances[active_donation_balance_id].balance += amount;
-        
-        if donater_info_account.total_sum > fundraising_account.top_donaters[2].map_or(0, |x| x.total_sum){
-            let top_donater_position = donation_account.top_donaters.iter()
-            .position(|x|x.map_or(false, |v| v.donater == donater_info_account.donater));
+        let donater_top_info_account = &mut ctx.accounts.donater_top_info;
+        donater_top_info_account.donater = donater_account.key();
+        donater_top_info_account.bump = *ctx.bumps.get("donater_top_info").ok_or(DonationError::BumpNotFound)?;
+
+        let donater_pending_reward_account = &mut ctx.accounts.donater_pending_reward;
+        donater_pending_reward_account.owner = donater_account.key();
+        donater_pending_reward_account.bump = *ctx.bumps.get("donater_pending_reward").ok_or(DonationError::BumpNotFound)?;
+
+        let referrer_vesting_account = &mut ctx.accounts.referrer_vesting;
+        referrer_vesting_account.owner = ctx.accounts.referrer_chrt_account.owner;
+        referrer_vesting_account.bump = *ctx.bumps.get("referrer_vesting").ok_or(DonationError::BumpNotFound)?;
+
+        emit!(SubscriptionCreated {
+            fundraising_id,
+            donater: donater_account.key(),
+            amount,
+            interval_seconds,
+            balance: deposit
+        });
+        Ok(())
+    }
 
-            if let Some(top_donater_position) = top_donater_position {
-                donation_account.top_donaters[top_donater_position].unwrap().total_sum += amount;
-            } else {
-                let mut top_donaters = [
-                    fundraising_account.top_donaters[0], 
-                    fundraising_account.top_donaters[1], 
-                    Some(DonaterTopInfo{ total_sum: donater_info_account.total_sum, donater: ctx.accounts.donater.key() }), 
-                    fundraising_account.top_donaters[2]
-                ];
-                top_donaters.sort_by(|b, a|{
-                    let a_sum = a.map_or(0, |x|x.total_sum);
-                    let b_sum = b.map_or(0, |x|x.total_sum);
-                    a_sum.cmp(&b_sum)
-                });
-                fundraising_account.top_donaters[0] = top_donaters[0];
-                fundraising_account.top_donaters[1] = top_donaters[1];
-                fundraising_account.top_donaters[2] = top_donaters[2];
-            }
-        }
+    pub fn execute_subscription(ctx: Context<ExecuteSubscription>, fundraising_id: u64) -> Result<()> {
+        require!(!ctx.accounts.donation_service.paused, DonationError::ServicePaused);
 
-        if donater_info_account.total_sum > donation_account.top_donaters[9].map_or(0, |x| x.total_sum) {
-            let top_donater_position = donation_account.top_donaters.iter()
-                .position(|x|x.map_or(false, |v| v.donater == donater_info_account.donater));
-            
-            if let Some(top_donater_position) = top_donater_position {
-                donation_account.top_donaters[top_donater_position].unwrap().total_sum += amount;
-            } else {
-                let mut top_donaters = [
-                    donation_account.top_donaters.to_vec(),
-                    [Some(DonaterTopInfo{ total_sum: donater_info_account.total_sum, donater: donater_info_account.donater })].to_vec()
-                ].concat();
-
-                top_donaters.sort_by(|b, a|{
-                    let a_sum = a.map_or(0, |x|x.total_sum);
-                    let b_sum = b.map_or(0, |x|x.total_sum);
-                    a_sum.cmp(&b_sum)
-                });
-                for i in 0..10 {
-                    donation_account.top_donaters[i] = top_donaters[i];
-                }
-            }
-        }
+        let fundraising_account = &mut ctx.accounts.fundraising;
 
-        if donater_top_info_account.nominated_sum > donation_account.nominated_donaters[9].map_or(0, |x| x.total_sum) {
-            let top_donater_position = donation_account.nominated_donaters.iter()
-                .position(|x|x.map_or(false, |v| v.donater == donater_info_account.donater));
-            
-            if let Some(top_donater_position) = top_donater_position {
-                donation_account.nominated_donaters[top_donater_position].unwrap().total_sum += amount;
-            } else {
-                let mut nominated_donaters = [
-                    donation_account.nominated_donaters.to_vec(),
-                    [Some(DonaterTopInfo{ total_sum: donater_top_info_account.nominated_sum, donater: donater_info_account.donater })].to_vec()
-                ].concat();
-
-                nominated_donaters.sort_by(|b, a|{
-                    let a_sum = a.map_or(0, |x|x.total_sum);
-                    let b_sum = b.map_or(0, |x|x.total_sum);
-                    a_sum.cmp(&b_sum)
-                });
-                for i in 0..10 {
-                    donation_account.nominated_donaters[i] = nominated_donaters[i];
-                }
-            }
-        }
+        require!(fundraising_account.is_active(), DonationError::FundraisingFinished);
+        require!(!fundraising_account.is_paused, DonationError::FundraisingPaused);
 
-        let state_bump = donation_account.bump.to_le_bytes();
+        let subscription_account = &mut ctx.accounts.subscription;
+        let current_time = current_timestamp()?;
+        let amount = subscription_account.amount;
+
+        require!(subscription_account.next_payment_at <= current_time, DonationError::SubscriptionNotDue);
+        require!(subscription_account.balance >= amount, DonationError::InsufficientSubscriptionBalance);
+
+        let donation_account = &mut ctx.accounts.donation_service;
+        let fee = record_donation(donation_account, fundraising_account, &mut ctx.accounts.donater_info, &mut ctx.accounts.donater_top_info, fundraising_id, amount)?;
+        let sum_to_donate = amount.checked_sub(fee).ok_or(DonationError::MathOverflow)?;
+
+        move_lamports(&subscription_account.to_account_info(), &fundraising_account.to_account_info(), sum_to_donate)?;
+        move_lamports(&subscription_account.to_account_info(), &donation_account.to_account_info(), fee)?;
+
+        // Missed periods are not charged retroactively
+        subscription_account.balance = subscription_account.balance.checked_sub(amount).ok_or(DonationError::MathOverflow)?;
+        subscription_account.next_payment_at = current_time.checked_add(subscription_account.interval_seconds).ok_or(DonationError::MathOverflow)?;
+        subscription_account.payments_num = subscription_account.payments_num.checked_add(1).ok_or(DonationError::MathOverflow)?;
+
+        emit!(SubscriptionExecuted {
+            fundraising_id,
+            donater: subscription_account.donater,
+            amount,
+            fee,
+            next_payment_at: subscription_account.next_payment_at,
+            remaining_balance: subscription_account.balance
+        });
+
+        reward_referrer(&ctx.accounts.donation_service, &mut ctx.accounts.referrer_vesting, &ctx.accounts.referrer_chrt_account, &ctx.accounts.chrt_mint, &ctx.accounts.token_program, amount)
+    }
+
+    pub fn cancel_subscription(ctx: Context<CancelSubscription>, fundraising_id: u64) -> Result<()> {
+        // Closing the account returns the remaining balance together with the rent
+        emit!(SubscriptionCanceled {
+            fundraising_id,
+            donater: ctx.accounts.donater.key(),
+            refunded_amount: ctx.accounts.subscription.balance
+        });
+        Ok(())
+    }
+
+    pub fn stake_chrt(ctx: Context<StakeCHRT>, amount: u64, fundraising_id: u64) -> Result<()> {
+        require!(!ctx.accounts.donation_service.paused, DonationError::ServicePaused);
+
+        let fundraising_account = &mut ctx.accounts.fundraising;
+        let staker_account = &ctx.accounts.staker;
+        let staker_token_account = &ctx.accounts.staker_token_account;
+        let fundraising_token_account = &ctx.accounts.fundraising_token_account;
+        let donation_account = &ctx.accounts.donation_service;
+        require!(amount > 0, DonationError::ZeroChrtAmount);
+        require!(fundraising_account.is_active(), DonationError::FundraisingFinished);
+        require!(!fundraising_account.is_paused, DonationError::FundraisingPaused);
+        require!(staker_token_account.mint == donation_account.token_mint, DonationError::InvalidTokenAccount);
+
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), 
+            Transfer{ from: staker_token_account.to_account_info(), to: fundraising_token_account.to_account_info(), authority: staker_account.to_account_info() }
+        );
+        token::transfer(cpi_ctx, amount)?;
+
+        let chrt_stake_account = &mut ctx.accounts.chrt_stake;
+        chrt_stake_account.owner = staker_account.key();
+        chrt_stake_account.fundraising_id = fundraising_id;
+        chrt_stake_account.bump = *ctx.bumps.get("chrt_stake").ok_or(DonationError::BumpNotFound)?;
+
+        chrt_stake_account.amount = chrt_stake_account.amount.checked_add(amount).ok_or(DonationError::MathOverflow)?;
+        fundraising_account.total_no_fee_chrt_sum = fundraising_account.total_no_fee_chrt_sum.checked_add(amount).ok_or(DonationError::MathOverflow)?;
+        fundraising_account.total_staked_chrt = fundraising_account.total_staked_chrt.checked_add(amount).ok_or(DonationError::MathOverflow)?;
+        Ok(())
+    }
+
+    pub fn unstake_chrt(ctx: Context<UnstakeCHRT>, fundraising_id: u64) -> Result<()> {
+        let fundraising_account = &mut ctx.accounts.fundraising;
+        let chrt_stake_account = &ctx.accounts.chrt_stake;
+        require!(!fundraising_account.is_active(), DonationError::FundraisingActive);
+
+        let fundraising_id_bytes = fundraising_id.to_le_bytes();
+        let fundraising_bump = fundraising_account.bump.to_le_bytes();
 
         let inner = vec![
-            b"state".as_ref(),
-            state_bump.as_ref()
+            b"fundraising".as_ref(),
+            fundraising_id_bytes.as_ref(),
+            fundraising_bump.as_ref()
         ];
         let outer = vec![inner.as_slice()];
 
         let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), 
-        MintTo{
-            to: ctx.accounts.referrer_chrt_account.to_account_info(),
-            mint: ctx.accounts.chrt_mint.to_account_info(),
-            authority: ctx.accounts.donation_service.to_account_info(),
-        }, outer.as_slice());
-        token::mint_to(cpi_ctx, amount * 101)?;
+            Transfer{ from: ctx.accounts.fundraising_token_account.to_account_info(), to: ctx.accounts.staker_token_account.to_account_info(), authority: fundraising_account.to_account_info() }, 
+            outer.as_slice()
+        );
+        token::transfer(cpi_ctx, chrt_stake_account.amount)?;
+
+        fundraising_account.total_staked_chrt = fundraising_account.total_staked_chrt.checked_sub(chrt_stake_account.amount).ok_or(DonationError::MathOverflow)?;
         Ok(())
     }
 
-    pub fn donate_chrt(ctx: Context<DonateCHRT>, amount: u64, _fundraising_id: u64, no_fee: bool) -> Result<()>{
+    pub fn open_cancel_proposal(ctx: Context<OpenCancelProposal>, fundraising_id: u64) -> Result<()> {
+        require!(!ctx.accounts.donation_service.paused, DonationError::ServicePaused);
+
         let fundraising_account = &mut ctx.accounts.fundraising;
-        let donater_account = &mut ctx.accounts.donater;
-        let donater_token_account = &mut ctx.accounts.donater_token_account;
-        let fundraising_token_account = &mut ctx.accounts.fundraising_token_account;
-        let donation_account = &mut ctx.accounts.donation_service;
-        require!(fundraising_token_account.mint == donation_account.token_mint, DonationError::InvalidTokenAccount);
-        require!(donater_token_account.mint == donation_account.token_mint, DonationError::InvalidTokenAccount);
+        let cancel_proposal_account = &mut ctx.accounts.cancel_proposal;
+        let donation_account = &ctx.accounts.donation_service;
+
+        require!(fundraising_account.is_active(), DonationError::FundraisingFinished);
+
+        let current_time = current_timestamp()?;
+        require!(fundraising_account.cancel_voting_ends_at <= current_time, DonationError::VotingInProgress);
+
+        // A passed cancellation can't be buried under a new proposal
+        require_no_passed_proposal(fundraising_account, ctx.remaining_accounts.get(0), donation_account.cancel_quorum_bps, ctx.program_id)?;
+
+        cancel_proposal_account.fundraising_id = fundraising_id;
+        cancel_proposal_account.proposal_id = fundraising_account.cancel_proposals_num;
+        cancel_proposal_account.proposer = ctx.accounts.proposer.key();
+        cancel_proposal_account.voting_ends_at = current_time.checked_add(donation_account.cancel_voting_period_seconds).ok_or(DonationError::MathOverflow)?;
+        cancel_proposal_account.grace_ends_at = cancel_proposal_account.voting_ends_at.checked_add(fundraising_account.cancel_grace_period_seconds).ok_or(DonationError::MathOverflow)?;
+        cancel_proposal_account.chrt_supply = ctx.accounts.chrt_mint.supply;
+        cancel_proposal_account.bump = *ctx.bumps.get("cancel_proposal").ok_or(DonationError::BumpNotFound)?;
+
+        fundraising_account.cancel_proposals_num = fundraising_account.cancel_proposals_num.checked_add(1).ok_or(DonationError::MathOverflow)?;
+        fundraising_account.cancel_voting_ends_at = cancel_proposal_account.grace_ends_at;
+
+        emit!(CancelProposalOpened {
+            fundraising_id,
+            proposal_id: cancel_proposal_account.proposal_id,
+            proposer: cancel_proposal_account.proposer,
+            voting_ends_at: cancel_proposal_account.voting_ends_at,
+            grace_ends_at: cancel_proposal_account.grace_ends_at
+        });
+        Ok(())
+    }
 
-        let state_bump = donation_account.bump.to_le_bytes();
+    pub fn appeal_cancel_proposal(ctx: Context<AppealCancelProposal>, fundraising_id: u64, proposal_id: u64, amount: u64) -> Result<()> {
+        require!(!ctx.accounts.donation_service.paused, DonationError::ServicePaused);
+
+        let cancel_proposal_account = &mut ctx.accounts.cancel_proposal;
+        let organizer_account = &ctx.accounts.organizer;
+        let donation_account = &ctx.accounts.donation_service;
+
+        require!(ctx.accounts.fundraising.owner == organizer_account.key(), DonationError::NotFundingOwner);
+        require!(ctx.accounts.fundraising.is_active(), DonationError::FundraisingFinished);
+        require!(amount > 0, DonationError::ZeroChrtAmount);
+        require!(!cancel_proposal_account.is_executed, DonationError::ProposalExecuted);
+
+        let current_time = current_timestamp()?;
+        require!(cancel_proposal_account.voting_ends_at <= current_time, DonationError::VotingInProgress);
+        require!(current_time < cancel_proposal_account.grace_ends_at, DonationError::GracePeriodEnded);
+        require!(cancel_proposal_account.is_voted_for(donation_account.cancel_quorum_bps), DonationError::ProposalNotPassed);
+
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), 
+            Transfer{ from: ctx.accounts.organizer_token_account.to_account_info(), to: ctx.accounts.proposal_token_account.to_account_info(), authority: organizer_account.to_account_info() }
+        );
+        token::transfer(cpi_ctx, amount)?;
+
+        cancel_proposal_account.appeal_stake = cancel_proposal_account.appeal_stake.checked_add(amount).ok_or(DonationError::MathOverflow)?;
+        ctx.accounts.fundraising.total_appeal_chrt = ctx.accounts.fundraising.total_appeal_chrt.checked_add(amount).ok_or(DonationError::MathOverflow)?;
+
+        emit!(CancelProposalAppealed {
+            fundraising_id,
+            proposal_id,
+            organizer: organizer_account.key(),
+            appeal_stake: cancel_proposal_account.appeal_stake,
+            is_blocked: cancel_proposal_account.is_blocked()
+        });
+        Ok(())
+    }
+
+    pub fn withdraw_appeal_stake(ctx: Context<AppealCancelProposal>, fundraising_id: u64, proposal_id: u64) -> Result<()> {
+        let cancel_proposal_account = &mut ctx.accounts.cancel_proposal;
+        let organizer_account = &ctx.accounts.organizer;
+
+        require!(ctx.accounts.fundraising.owner == organizer_account.key(), DonationError::NotFundingOwner);
+        require!(!cancel_proposal_account.is_appeal_withdrawn, DonationError::AppealStakeWithdrawn);
+        // The stake keeps the proposal blocked, so it stays locked until the fundraising is finished
+        require!(!ctx.accounts.fundraising.is_active(), DonationError::FundraisingActive);
+
+        let current_time = current_timestamp()?;
+        require!(cancel_proposal_account.grace_ends_at <= current_time, DonationError::GracePeriod);
+
+        let fundraising_id_bytes = fundraising_id.to_le_bytes();
+        let proposal_id_bytes = proposal_id.to_le_bytes();
+        let proposal_bump = cancel_proposal_account.bump.to_le_bytes();
 
         let inner = vec![
-            b"state".as_ref(),
-            state_bump.as_ref()
+            b"cancel-proposal".as_ref(),
+            fundraising_id_bytes.as_ref(),
+            proposal_id_bytes.as_ref(),
+            proposal_bump.as_ref()
         ];
         let outer = vec![inner.as_slice()];
 
         let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), 
-            Transfer{ from: donater_token_account.to_account_info(), to: fundraising_token_account.to_account_info(), authority: donater_account.to_account_info() }, 
+            Transfer{ from: ctx.accounts.proposal_token_account.to_account_info(), to: ctx.accounts.organizer_token_account.to_account_info(), authority: cancel_proposal_account.to_account_info() }, 
             outer.as_slice()
         );
-        token::transfer(cpi_ctx, amount)?;
-        if no_fee {
-            fundraising_account.total_no_fee_chrt_sum += amount;
+        token::transfer(cpi_ctx, cancel_proposal_account.appeal_stake)?;
+
+        cancel_proposal_account.is_appeal_withdrawn = true;
+        ctx.accounts.fundraising.total_appeal_chrt = ctx.accounts.fundraising.total_appeal_chrt.checked_sub(cancel_proposal_account.appeal_stake).ok_or(DonationError::MathOverflow)?;
+
+        emit!(AppealStakeWithdrawn {
+            fundraising_id,
+            proposal_id,
+            organizer: organizer_account.key(),
+            amount: cancel_proposal_account.appeal_stake
+        });
+        Ok(())
+    }
+
+    pub fn vote_cancel(ctx: Context<VoteCancelProposal>, _fundraising_id: u64, _proposal_id: u64, amount: u64) -> Result<()> {
+        vote_on_cancel_proposal(ctx, amount, true)
+    }
+
+    pub fn vote_keep(ctx: Context<VoteCancelProposal>, _fundraising_id: u64, _proposal_id: u64, amount: u64) -> Result<()> {
+        vote_on_cancel_proposal(ctx, amount, false)
+    }
+
+    pub fn refund_vote(ctx: Context<RefundVote>, fundraising_id: u64, proposal_id: u64) -> Result<()> {
+        let cancel_proposal_account = &ctx.accounts.cancel_proposal;
+        let cancel_vote_account = &ctx.accounts.cancel_vote;
+        let donation_account = &mut ctx.accounts.donation_service;
+
+        let current_time = current_timestamp()?;
+        require!(cancel_proposal_account.voting_ends_at <= current_time, DonationError::VotingInProgress);
+        require!(cancel_proposal_account.grace_ends_at <= current_time, DonationError::GracePeriod);
+
+        // Without quorum nobody wins, otherwise only the losing side gets its CHRT back
+        let (refund_amount, burn_amount) = if !cancel_proposal_account.has_quorum(donation_account.cancel_quorum_bps) {
+            (cancel_vote_account.cancel_amount.checked_add(cancel_vote_account.keep_amount).ok_or(DonationError::MathOverflow)?, 0)
+        } else if cancel_proposal_account.is_passed(donation_account.cancel_quorum_bps) {
+            (cancel_vote_account.keep_amount, cancel_vote_account.cancel_amount)
         } else {
-            fundraising_account.total_cancel_chrt_sum += amount;
+            (cancel_vote_account.cancel_amount, cancel_vote_account.keep_amount)
+        };
+
+        let fundraising_id_bytes = fundraising_id.to_le_bytes();
+        let proposal_id_bytes = proposal_id.to_le_bytes();
+        let proposal_bump = cancel_proposal_account.bump.to_le_bytes();
+
+        let inner = vec![
+            b"cancel-proposal".as_ref(),
+            fundraising_id_bytes.as_ref(),
+            proposal_id_bytes.as_ref(),
+            proposal_bump.as_ref()
+        ];
+        let outer = vec![inner.as_slice()];
+
+        if refund_amount > 0 {
+            let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), 
+                Transfer{ from: ctx.accounts.proposal_token_account.to_account_info(), to: ctx.accounts.voter_token_account.to_account_info(), authority: cancel_proposal_account.to_account_info() }, 
+                outer.as_slice()
+            );
+            token::transfer(cpi_ctx, refund_amount)?;
+        }
+
+        if burn_amount > 0 {
+            let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), 
+                Burn{ mint: ctx.accounts.chrt_mint.to_account_info(), from: ctx.accounts.proposal_token_account.to_account_info(), authority: cancel_proposal_account.to_account_info() }, 
+                outer.as_slice()
+            );
+            token::burn(cpi_ctx, burn_amount)?;
+            donation_account.total_burned_cancel_chrt = donation_account.total_burned_cancel_chrt.checked_add(burn_amount).ok_or(DonationError::MathOverflow)?;
         }
         Ok(())
     }
 
-    pub fn withdraw(ctx: Context<Withdraw>, fundraising_id: u64) -> Result<()> {
+    pub fn withdraw<'info>(ctx: Context<'_, '_, '_, 'info, Withdraw<'info>>, fundraising_id: u64) -> Result<()> {
+        require!(ctx.accounts.fundraising.withdrawal_threshold == 0, DonationError::WithdrawalApprovalRequired);
+
+        withdraw_fundraising(ctx.accounts, ctx.remaining_accounts, fundraising_id, ctx.program_id)
+    }
+
+    pub fn set_withdrawal_signers(ctx: Context<SetWithdrawalSigners>, _fundraising_id: u64, signers: Vec<Pubkey>, threshold: u8) -> Result<()> {
+        require!(!ctx.accounts.donation_service.paused, DonationError::ServicePaused);
+
         let fundraising_account = &mut ctx.accounts.fundraising;
-        let fundraising_owner_account = &mut ctx.accounts.fundraising_owner;
+
+        require!(fundraising_account.is_active(), DonationError::FundraisingFinished);
+        // Otherwise the owner alone could swap the signers and bypass the approvals
+        require!(fundraising_account.withdrawal_threshold == 0, DonationError::WithdrawalSignersLocked);
+        require!(signers.len() <= MAX_WITHDRAWAL_SIGNERS, DonationError::TooManyWithdrawalSigners);
+        require!(threshold as usize <= signers.len(), DonationError::InvalidWithdrawalThreshold);
+        require!(signers.iter().enumerate().all(|(i, x)| !signers[..i].contains(x)), DonationError::InvalidWithdrawalThreshold);
+
+        fundraising_account.withdrawal_signers = signers;
+        fundraising_account.withdrawal_threshold = threshold;
+        Ok(())
+    }
+
+    pub fn create_withdrawal_request(ctx: Context<CreateWithdrawalRequest>, fundraising_id: u64) -> Result<()> {
+        require!(!ctx.accounts.donation_service.paused, DonationError::ServicePaused);
+
+        let withdrawal_request_account = &mut ctx.accounts.withdrawal_request;
+        let proposer_account = &ctx.accounts.proposer;
+
+        require!(ctx.accounts.fundraising.is_active(), DonationError::FundraisingFinished);
+
+        withdrawal_request_account.fundraising_id = fundraising_id;
+        withdrawal_request_account.proposer = proposer_account.key();
+        withdrawal_request_account.approvals = vec![proposer_account.key()];
+        withdrawal_request_account.bump = *ctx.bumps.get("withdrawal_request").ok_or(DonationError::BumpNotFound)?;
+
+        emit!(WithdrawalRequested {
+            fundraising_id,
+            proposer: proposer_account.key()
+        });
+        Ok(())
+    }
+
+    pub fn approve_withdrawal(ctx: Context<ApproveWithdrawal>, fundraising_id: u64) -> Result<()> {
+        require!(!ctx.accounts.donation_service.paused, DonationError::ServicePaused);
+
+        let withdrawal_request_account = &mut ctx.accounts.withdrawal_request;
+        let fundraising_account = &ctx.accounts.fundraising;
+        let approver_account = &ctx.accounts.approver;
+
+        require!(fundraising_account.is_active(), DonationError::FundraisingFinished);
+        require!(!withdrawal_request_account.approvals.contains(&approver_account.key()), DonationError::AlreadyApproved);
+
+        // Drop approvals of removed signers so that the list stays bounded by the signer set
+        withdrawal_request_account.approvals.retain(|x| fundraising_account.withdrawal_signers.contains(x));
+        withdrawal_request_account.approvals.push(approver_account.key());
+
+        emit!(WithdrawalApproved {
+            fundraising_id,
+            approver: approver_account.key(),
+            approvals_num: withdrawal_request_account.approvals_num(&fundraising_account.withdrawal_signers) as u64,
+            threshold: fundraising_account.withdrawal_threshold
+        });
+        Ok(())
+    }
+
+    pub fn execute_withdrawal<'info>(ctx: Context<'_, '_, '_, 'info, ExecuteWithdrawal<'info>>, fundraising_id: u64) -> Result<()> {
+        let fundraising_account = &ctx.accounts.withdraw.fundraising;
+        let approvals_num = ctx.accounts.withdrawal_request.approvals_num(&fundraising_account.withdrawal_signers);
+
+        require!(approvals_num >= fundraising_account.withdrawal_threshold as usize, DonationError::NotEnoughApprovals);
+
+        withdraw_fundraising(&mut ctx.accounts.withdraw, ctx.remaining_accounts, fundraising_id, ctx.program_id)
+    }
+
+    pub fn cancel_fundraising(ctx: Context<CancelFundraising>, fundraising_id: u64, _proposal_id: u64) -> Result<()> {
+        require!(!ctx.accounts.donation_service.paused, DonationError::ServicePaused);
+
         let donation_account = &mut ctx.accounts.donation_service;
+        let fundraising_account = &mut ctx.accounts.fundraising;
 
-        fundraising_account.is_finished = true;
+        let cancel_proposal_account = &mut ctx.accounts.cancel_proposal;
 
-        let active_donation_balance_id = donation_account.active_fundraising_balances.binary_search_by(|x|x.id.cmp(&fundraising_id)).unwrap();
-        donation_account.active_fundraising_balances.remove(active_donation_balance_id);
+        require!(fundraising_account.is_active(), DonationError::FundraisingFinished);
+        require!(!cancel_proposal_account.is_executed, DonationError::ProposalExecuted);
+
+        let current_time = current_timestamp()?;
+        require!(cancel_proposal_account.voting_ends_at <= current_time, DonationError::VotingInProgress);
+        require!(cancel_proposal_account.grace_ends_at <= current_time, DonationError::GracePeriod);
+        require!(cancel_proposal_account.is_passed(donation_account.cancel_quorum_bps), DonationError::ProposalNotPassed);
+
+        cancel_proposal_account.is_executed = true;
+
+        fundraising_account.transition_to(FundraisingStatus::Canceled)?;
+        let active_donation_balance_id = donation_account.active_fundraising_balances.binary_search_by(|x|x.id.cmp(&fundraising_id)).map_err(|_| DonationError::FundraisingNotActive)?;
+        let active_balance = donation_account.active_fundraising_balances.remove(active_donation_balance_id);
+        settle_redistribution(fundraising_account, donation_account, active_balance.pending_redistribution)?;
+
+        // Only the net sum is held by the fundraising, the fee already went to the platform
+        let balance_to_redistribute = fundraising_account.total_sum;
 
-        require!(fundraising_account.owner == fundraising_owner_account.key(), DonationError::NotFundingOwner);
-                
-        **fundraising_account.to_account_info().try_borrow_mut_lamports()? -= fundraising_account.total_sum;
-        **fundraising_owner_account.to_account_info().try_borrow_mut_lamports()? += fundraising_account.total_sum;
-    
-        fundraising_account.total_sum = 0;
+        donation_account.total_canceled_funds = donation_account.total_canceled_funds.checked_add(balance_to_redistribute).ok_or(DonationError::MathOverflow)?;
+
+        let weights: Vec<u64> = match donation_account.redistribution_policy {
+            RedistributionPolicy::Proportional => donation_account.active_fundraising_balances.iter().map(|x| x.balance).collect(),
+            RedistributionPolicy::EqualSplit => vec![1; donation_account.active_fundraising_balances.len()],
+            RedistributionPolicy::RefundDonaters | RedistributionPolicy::ReserveFund => vec![],
+        };
+
+        if donation_account.redistribution_policy == RedistributionPolicy::RefundDonaters {
+            fundraising_account.refund_pool = fundraising_account.total_sum;
+        } else if weights.iter().any(|x| *x > 0) {
+            redistribute_canceled_balance(fundraising_account, donation_account, &weights)?;
+        } else {
+            // Reserve fund is also the fallback when there is no active balance to redistribute to
+            move_lamports(&fundraising_account.to_account_info(), &ctx.accounts.reserve_fund.to_account_info(), fundraising_account.total_sum)?;
+
+            fundraising_account.total_sum = 0;
+        }
+
+        forfeit_creation_deposit(fundraising_account, donation_account, &ctx.accounts.fundraising_token_account.to_account_info(), &ctx.accounts.chrt_mint, &ctx.accounts.token_program)?;
+
+        emit!(FundraisingCanceled {
+            fundraising_id,
+            proposal_id: cancel_proposal_account.proposal_id,
+            redistributed_sum: balance_to_redistribute
+        });
         Ok(())
     }
 
-    pub fn cancel_fundraising(ctx: Context<CancelFundraising>, fundraising_id: u64) -> Result<()> {
+    pub fn expire_fundraising(ctx: Context<ExpireFundraising>, fundraising_id: u64) -> Result<()> {
+        require!(!ctx.accounts.donation_service.paused, DonationError::ServicePaused);
+
         let donation_account = &mut ctx.accounts.donation_service;
         let fundraising_account = &mut ctx.accounts.fundraising;
 
-        require!(!fundraising_account.is_finished, DonationError::FundraisingFinished);
-        require!(fundraising_account.total_cancel_chrt_sum > donation_account.cancel_chrt_threshold, DonationError::InsufficientChrtAmount);
-        
-        fundraising_account.is_finished = true;
-        let active_donation_balance_id = donation_account.active_fundraising_balances.binary_search_by(|x|x.id.cmp(&fundraising_id)).unwrap();
-        let balance_to_redistribute = donation_account.active_fundraising_balances[active_donation_balance_id].balance;
-        donation_account.active_fundraising_balances.remove(active_donation_balance_id);
+        require!(donation_account.expiry_period_seconds > 0, DonationError::ExpiryDisabled);
+        require!(fundraising_account.is_active(), DonationError::FundraisingFinished);
+
+        let expires_at = fundraising_account.last_activity_at.checked_add(donation_account.expiry_period_seconds).ok_or(DonationError::MathOverflow)?;
+        require!(expires_at <= current_timestamp()?, DonationError::FundraisingNotStale);
+
+        // Votes are refunded without the fundraising account, only an undecided or passed proposal still needs it
+        require!(fundraising_account.cancel_voting_ends_at <= current_timestamp()?, DonationError::VotingInProgress);
+        require_no_passed_proposal(fundraising_account, ctx.remaining_accounts.get(0), donation_account.cancel_quorum_bps, ctx.program_id)?;
 
-        let total_sum: u128 = donation_account.active_fundraising_balances.iter().map(|x| x.balance as u128).sum();
+        let active_donation_balance_id = donation_account.active_fundraising_balances.binary_search_by(|x|x.id.cmp(&fundraising_id)).map_err(|_| DonationError::FundraisingNotActive)?;
+        let pending_redistribution = donation_account.active_fundraising_balances[active_donation_balance_id].pending_redistribution;
+
+        // Stakes and appeals are returned through the fundraising account, so it can only be closed once nothing is locked
+        require!(fundraising_account.total_sum == 0
+            && donation_account.active_fundraising_balances[active_donation_balance_id].balance == pending_redistribution
+            && fundraising_account.total_staked_chrt == 0
+            && fundraising_account.total_appeal_chrt == 0, DonationError::FundraisingNotEmpty);
+
+        donation_account.active_fundraising_balances.remove(active_donation_balance_id);
+        fundraising_account.transition_to(FundraisingStatus::Expired)?;
+
+        // An unclaimed redistributed share already sits in the service account and is forfeited along with the deposit
+        donation_account.total_fee = donation_account.total_fee.checked_add(pending_redistribution).ok_or(DonationError::MathOverflow)?;
+
+        forfeit_creation_deposit(fundraising_account, donation_account, &ctx.accounts.fundraising_token_account.to_account_info(), &ctx.accounts.chrt_mint, &ctx.accounts.token_program)?;
+
+        // The fundraising ATA only exists once CHRT was deposited, staked or sent to it
+        let fundraising_token_info = ctx.accounts.fundraising_token_account.to_account_info();
+        if !fundraising_token_info.data_is_empty() {
+            let fundraising_bump = fundraising_account.bump.to_le_bytes();
+            let fundraising_id_bytes = fundraising_id.to_le_bytes();
+
+            let inner = vec![
+                b"fundraising".as_ref(),
+                fundraising_id_bytes.as_ref(),
+                fundraising_bump.as_ref()
+            ];
+            let outer = vec![inner.as_slice()];
+
+            // Nothing is staked anymore, so whatever else was sent to the fundraising would otherwise keep its token account open
+            let unsolicited_chrt = Account::<TokenAccount>::try_from(&fundraising_token_info)?.amount;
+            if unsolicited_chrt > 0 {
+                let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(),
+                    Burn{ mint: ctx.accounts.chrt_mint.to_account_info(), from: fundraising_token_info.clone(), authority: fundraising_account.to_account_info() },
+                    outer.as_slice()
+                );
+                token::burn(cpi_ctx, unsolicited_chrt)?;
+                donation_account.total_burned_forfeited_chrt = donation_account.total_burned_forfeited_chrt.checked_add(unsolicited_chrt).ok_or(DonationError::MathOverflow)?;
+            }
 
-        for active_balance in &mut donation_account.active_fundraising_balances {
-            active_balance.balance += (balance_to_redistribute as u128 * active_balance.balance as u128 / total_sum) as u64;
+            let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(),
+                CloseAccount{ account: fundraising_token_info, destination: ctx.accounts.owner.to_account_info(), authority: fundraising_account.to_account_info() },
+                outer.as_slice()
+            );
+            token::close_account(cpi_ctx)?;
         }
+
+        emit!(FundraisingExpired {
+            fundraising_id,
+            owner: fundraising_account.owner,
+            last_activity_at: fundraising_account.last_activity_at
+        });
+        Ok(())
+    }
+
+    pub fn claim_refund(ctx: Context<ClaimRefund>, fundraising_id: u64) -> Result<()> {
+        let donater_info_account = &mut ctx.accounts.donater_info;
+        let fundraising_account = &mut ctx.accounts.fundraising;
+
+        require!(fundraising_account.status == FundraisingStatus::Canceled, DonationError::NothingToRefund);
+        require!(fundraising_account.refund_pool > 0, DonationError::NothingToRefund);
+        require!(!donater_info_account.is_refunded, DonationError::AlreadyRefunded);
+
+        let refund_amount = (donater_info_account.total_sum as u128 * fundraising_account.refund_pool as u128 / fundraising_account.total_donated as u128) as u64;
+
+        move_lamports(&fundraising_account.to_account_info(), &ctx.accounts.donater.to_account_info(), refund_amount)?;
+
+        fundraising_account.total_sum = fundraising_account.total_sum.checked_sub(refund_amount).ok_or(DonationError::MathOverflow)?;
+        donater_info_account.is_refunded = true;
+
+        emit!(DonationRefunded {
+            fundraising_id,
+            donater: donater_info_account.donater,
+            amount: refund_amount
+        });
+        Ok(())
+    }
+
+    // Permissionless, the share can only ever move into the fundraising it was assigned to
+    pub fn claim_redistribution(ctx: Context<ClaimRedistribution>, fundraising_id: u64) -> Result<()> {
+        require!(!ctx.accounts.donation_service.paused, DonationError::ServicePaused);
+
+        let donation_account = &mut ctx.accounts.donation_service;
+        let fundraising_account = &mut ctx.accounts.fundraising;
+
+        let active_donation_balance_id = donation_account.active_fundraising_balances.binary_search_by(|x|x.id.cmp(&fundraising_id)).map_err(|_| DonationError::FundraisingNotActive)?;
+        let amount = donation_account.active_fundraising_balances[active_donation_balance_id].pending_redistribution;
+        require!(amount > 0, DonationError::NothingToRedistribute);
+
+        donation_account.active_fundraising_balances[active_donation_balance_id].pending_redistribution = 0;
+        settle_redistribution(fundraising_account, donation_account, amount)?;
+
+        emit!(RedistributionClaimed {
+            fundraising_id,
+            amount
+        });
         Ok(())
     }
+
     pub fn withdraw_fee(ctx: Context<WithdrawFee>) -> Result<()> {
         let donation_account = &mut ctx.accounts.donation_service;
         let service_owner_account = &mut ctx.accounts.donation_service_owner;
 
         require!(service_owner_account.key() == donation_account.owner, DonationError::NotOwner);
 
-        **donation_account.to_account_info().try_borrow_mut_lamports()? -= donation_account.total_fee;
-        **service_owner_account.to_account_info().try_borrow_mut_lamports()? += donation_account.total_fee;
+        move_lamports(&donation_account.to_account_info(), &service_owner_account.to_account_info(), donation_account.total_fee)?;
 
         donation_account.total_fee = 0;
         Ok(())
     }
 
     pub fn reward_top_donaters(ctx: Context<RewardTopDonaters>) -> Result<()> {
+        require!(!ctx.accounts.donation_service.paused, DonationError::ServicePaused);
+
         let donation_account = &mut ctx.accounts.donation_service;
-        require!(ctx.accounts.owner.key() == donation_account.owner, DonationError::NotOwner);
+        if !donation_account.permissionless_rewards {
+            require!(ctx.accounts.cranker.key() == donation_account.owner, DonationError::NotOwner);
+        }
+
+        // A repeated account would be saved once per slot and the stale copy would overwrite the rewarded one,
+        // top infos are derived from the pending reward owners so distinct pending rewards are enough
+        let pending_reward_keys = [ctx.accounts.top_1_pending_reward.key(), ctx.accounts.top_2_pending_reward.key(), ctx.accounts.top_3_pending_reward.key()];
+        require!(pending_reward_keys.iter().enumerate().all(|(i, x)| !pending_reward_keys[..i].contains(x)), DonationError::DuplicateRewardAccounts);
 
-        let current_time = Clock::get().unwrap().unix_timestamp as u64;
+        let current_time = current_timestamp()?;
 
-        require!(donation_account.reward_cooldown <= current_time, DonationError::TooEarly);
+        require!(donation_account.next_reward_at <= current_time, DonationError::TooEarly);
 
-        let wallets = [&ctx.accounts.top_1_wallet, &ctx.accounts.top_2_wallet, &ctx.accounts.top_3_wallet];
+        let pending_rewards = [&mut ctx.accounts.top_1_pending_reward, &mut ctx.accounts.top_2_pending_reward, &mut ctx.accounts.top_3_pending_reward];
         let top_infos = [&mut ctx.accounts.top_1_info, &mut ctx.accounts.top_2_info, &mut ctx.accounts.top_3_info];
+        let reward_epoch_account = &mut ctx.accounts.reward_epoch;
 
         for (i, top_donater) in donation_account.nominated_donaters[0..3].iter().enumerate() {
             if let Some(top_donater) = top_donater {
-            
-                let state_bump = donation_account.bump.to_le_bytes();
-
-                let inner = vec![
-                    b"state".as_ref(),
-                    state_bump.as_ref()
-                ];
-                let outer = vec![inner.as_slice()];
-                
-                require!(wallets[i].owner == top_donater.donater, DonationError::InvalidWalletAccount);
-
-                let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), 
-                MintTo { 
-                    mint: ctx.accounts.chrt_mint.to_account_info(), 
-                    to: wallets[i].to_account_info(), 
-                    authority: donation_account.to_account_info() 
-                }, &outer);
-
-                token::mint_to(cpi_ctx, donation_account.reward_chrt_amount)?;
-                donation_account.nominated_donaters[i].unwrap().total_sum = 0;
+                require!(pending_rewards[i].owner == top_donater.donater, DonationError::InvalidWalletAccount);
+
+                pending_rewards[i].amount = pending_rewards[i].amount.checked_add(donation_account.reward_chrt_amount).ok_or(DonationError::MathOverflow)?;
+                reward_epoch_account.rewarded_donaters[i] = Some(DonaterTopInfo { total_sum: top_infos[i].nominated_sum, donater: top_donater.donater });
                 top_infos[i].nominated_sum = 0;
             }
         }
-        donation_account.reward_cooldown = current_time;
+
+        // Rewarded donaters leave the board, the rest keep their sums for the next epoch
+        let mut nominated_donaters = donation_account.nominated_donaters[3..].to_vec();
+        nominated_donaters.resize(10, None);
+        nominated_donaters.sort_by(|b, a|{
+            let a_sum = a.map_or(0, |x|x.total_sum);
+            let b_sum = b.map_or(0, |x|x.total_sum);
+            a_sum.cmp(&b_sum)
+        });
+        for i in 0..10 {
+            donation_account.nominated_donaters[i] = nominated_donaters[i];
+        }
+
+        reward_epoch_account.epoch = donation_account.reward_epoch;
+        reward_epoch_account.rewarded_at = current_time;
+        reward_epoch_account.reward_chrt_amount = donation_account.reward_chrt_amount;
+        reward_epoch_account.bump = *ctx.bumps.get("reward_epoch").ok_or(DonationError::BumpNotFound)?;
+
+        if donation_account.crank_tip_chrt_amount > 0 {
+            let state_bump = donation_account.bump.to_le_bytes();
+
+            let inner = vec![
+                b"state".as_ref(),
+                state_bump.as_ref()
+            ];
+            let outer = vec![inner.as_slice()];
+
+            let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), 
+            MintTo { 
+                mint: ctx.accounts.chrt_mint.to_account_info(), 
+                to: ctx.accounts.cranker_chrt_account.to_account_info(), 
+                authority: donation_account.to_account_info() 
+            }, &outer);
+
+            token::mint_to(cpi_ctx, donation_account.crank_tip_chrt_amount)?;
+        }
+
+        donation_account.reward_epoch = donation_account.reward_epoch.checked_add(1).ok_or(DonationError::MathOverflow)?;
+        donation_account.next_reward_at = current_time.checked_add(donation_account.reward_period_seconds).ok_or(DonationError::MathOverflow)?;
+        Ok(())
+    }
+
+    pub fn claim_rewards(ctx: Context<ClaimRewards>) -> Result<()> {
+        require!(!ctx.accounts.donation_service.paused, DonationError::ServicePaused);
+
+        let pending_reward_account = &mut ctx.accounts.pending_reward;
+        let donation_account = &ctx.accounts.donation_service;
+
+        let amount = pending_reward_account.amount;
+        require!(amount > 0, DonationError::NothingToClaim);
+
+        pending_reward_account.amount = 0;
+        pending_reward_account.total_claimed = pending_reward_account.total_claimed.checked_add(amount).ok_or(DonationError::MathOverflow)?;
+
+        let vesting_account = &mut ctx.accounts.vesting_account;
+        vesting_account.owner = ctx.accounts.user.key();
+        vesting_account.bump = *ctx.bumps.get("vesting_account").ok_or(DonationError::BumpNotFound)?;
+
+        if donation_account.vesting_duration_seconds > 0 {
+            let current_time = current_timestamp()?;
+            vesting_account.credit(amount, donation_account.vesting_cliff_seconds, donation_account.vesting_duration_seconds, current_time)?;
+            return Ok(());
+        }
+
+        let state_bump = donation_account.bump.to_le_bytes();
+
+        let inner = vec![
+            b"state".as_ref(),
+            state_bump.as_ref()
+        ];
+        let outer = vec![inner.as_slice()];
+
+        let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), 
+        MintTo { 
+            mint: ctx.accounts.chrt_mint.to_account_info(), 
+            to: ctx.accounts.user_chrt_account.to_account_info(), 
+            authority: donation_account.to_account_info() 
+        }, &outer);
+
+        token::mint_to(cpi_ctx, amount)?;
+        Ok(())
+    }
+
+    pub fn release_vested(ctx: Context<ReleaseVested>) -> Result<()> {
+        require!(!ctx.accounts.donation_service.paused, DonationError::ServicePaused);
+
+        let vesting_account = &mut ctx.accounts.vesting_account;
+        let donation_account = &ctx.accounts.donation_service;
+
+        let current_time = current_timestamp()?;
+        let cliff_seconds = donation_account.vesting_cliff_seconds;
+        let duration_seconds = donation_account.vesting_duration_seconds;
+
+        let amount = vesting_account.releasable(cliff_seconds, duration_seconds, current_time)?;
+        require!(amount > 0, DonationError::NothingToRelease);
+
+        vesting_account.mark_released(cliff_seconds, duration_seconds, current_time);
+        vesting_account.total_released = vesting_account.total_released.checked_add(amount).ok_or(DonationError::MathOverflow)?;
+
+        let state_bump = donation_account.bump.to_le_bytes();
+
+        let inner = vec![
+            b"state".as_ref(),
+            state_bump.as_ref()
+        ];
+        let outer = vec![inner.as_slice()];
+
+        let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), 
+        MintTo { 
+            mint: ctx.accounts.chrt_mint.to_account_info(), 
+            to: ctx.accounts.user_chrt_account.to_account_info(), 
+            authority: donation_account.to_account_info() 
+        }, &outer);
+
+        token::mint_to(cpi_ctx, amount)?;
         Ok(())
     }
 }
\ No newline at end of file
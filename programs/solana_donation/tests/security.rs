@@ -0,0 +1,1149 @@
+use anchor_lang::{error::ErrorCode, AccountDeserialize, InstructionData, ToAccountMetas};
+use anchor_spl::{
+    associated_token::get_associated_token_address,
+    token::TokenAccount,
+    token::spl_token::{self, solana_program::{program_option::COption, program_pack::Pack}},
+};
+use solana_donation::{DonationError, DonationService, Fundraising, FundraisingCategory, FundraisingStatus, RedistributionPolicy};
+use solana_program_test::{processor, BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::Account,
+    clock::Clock,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction, system_program, sysvar,
+    transaction::{Transaction, TransactionError},
+};
+
+const DONATED_AMOUNT: u64 = 1_000_000;
+// Part of the donation held by the fundraising after the 1% platform fee
+const DONATED_SUM: u64 = DONATED_AMOUNT - DONATED_AMOUNT / 100;
+const CANCEL_VOTING_PERIOD_SECONDS: u64 = 3;
+const CANCEL_GRACE_PERIOD_SECONDS: u64 = 5;
+
+fn state_pda() -> Pubkey {
+    Pubkey::find_program_address(&[b"state"], &solana_donation::id()).0
+}
+
+fn fundraising_pda(fundraising_id: u64) -> Pubkey {
+    Pubkey::find_program_address(&[b"fundraising", fundraising_id.to_le_bytes().as_ref()], &solana_donation::id()).0
+}
+
+fn cancel_proposal_pda(fundraising_id: u64, proposal_id: u64) -> Pubkey {
+    Pubkey::find_program_address(&[b"cancel-proposal", fundraising_id.to_le_bytes().as_ref(), proposal_id.to_le_bytes().as_ref()], &solana_donation::id()).0
+}
+
+fn chrt_mint() -> Pubkey {
+    Pubkey::new_from_array([1; 32])
+}
+
+struct TestEnv {
+    context: ProgramTestContext,
+    fundraising_owner: Keypair,
+    beneficiary: Keypair,
+    donater: Keypair,
+    // Receives the referral CHRT of every donation and uses it to vote
+    referrer: Keypair,
+    referrer_token_account: Keypair,
+    // Referral account of the subscriptions and source of the appeal stake
+    fundraising_owner_token_account: Keypair,
+    // Never pays fees, so its balance only moves by what the program sends it
+    reserve_fund: Keypair,
+}
+
+async fn process(context: &mut ProgramTestContext, instructions: &[Instruction], signers: &[&Keypair]) -> Result<(), BanksClientError> {
+    let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+
+    let transaction = Transaction::new_signed_with_payer(instructions, Some(&context.payer.pubkey()), &all_signers, blockhash);
+    context.banks_client.process_transaction(transaction).await
+}
+
+fn assert_custom_error(result: Result<(), BanksClientError>, code: u32, name: String) {
+    match result {
+        Err(BanksClientError::TransactionError(TransactionError::InstructionError(0, InstructionError::Custom(actual)))) => {
+            assert_eq!(actual, code, "Transaction should fail with {}", name);
+        }
+        other => panic!("Transaction should fail with {}, got {:?}", name, other),
+    }
+}
+
+fn assert_donation_error(result: Result<(), BanksClientError>, error: DonationError) {
+    assert_custom_error(result, u32::from(error), error.to_string());
+}
+
+fn assert_anchor_error(result: Result<(), BanksClientError>, error: ErrorCode) {
+    assert_custom_error(result, u32::from(error), error.to_string());
+}
+
+async fn get_state<T: AccountDeserialize>(context: &mut ProgramTestContext, address: Pubkey) -> T {
+    let account = context.banks_client.get_account(address).await.unwrap().unwrap();
+    T::try_deserialize(&mut account.data.as_slice()).unwrap()
+}
+
+async fn warp_clock(context: &mut ProgramTestContext, seconds: u64) {
+    let mut clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    clock.unix_timestamp += seconds as i64;
+    context.set_sysvar(&clock);
+}
+
+// Creates the service under the given redistribution policy with two fundraisings, each funded by a real DONATED_AMOUNT donation
+async fn setup(redistribution_policy: RedistributionPolicy) -> TestEnv {
+    let mut program_test = ProgramTest::new("solana_donation", solana_donation::id(), processor!(solana_donation::entry));
+
+    // CHRT mint owned by the state PDA, as in the deployed setup
+    let mut mint_data = vec![0; spl_token::state::Mint::LEN];
+    spl_token::state::Mint::pack(spl_token::state::Mint {
+        mint_authority: COption::Some(state_pda()),
+        supply: 0,
+        decimals: 3,
+        is_initialized: true,
+        freeze_authority: COption::None,
+    }, &mut mint_data).unwrap();
+    program_test.add_account(chrt_mint(), Account {
+        lamports: 1_000_000_000,
+        data: mint_data,
+        owner: spl_token::id(),
+        ..Account::default()
+    });
+
+    let mut env = TestEnv {
+        context: program_test.start_with_context().await,
+        fundraising_owner: Keypair::new(),
+        beneficiary: Keypair::new(),
+        donater: Keypair::new(),
+        referrer: Keypair::new(),
+        referrer_token_account: Keypair::new(),
+        fundraising_owner_token_account: Keypair::new(),
+        reserve_fund: Keypair::new(),
+    };
+    let service_owner = env.context.payer.pubkey();
+
+    let initialize = Instruction {
+        program_id: solana_donation::id(),
+        accounts: solana_donation::accounts::Initialize {
+            donation_service: state_pda(),
+            owner: service_owner,
+            system_program: system_program::id(),
+        }.to_account_metas(None),
+        data: solana_donation::instruction::Initialize {
+            reward_period_seconds: 1,
+            owner_fee_percent: 1,
+            reward_chrt_amount: 2,
+            no_fee_chrt_threshold: 1,
+            cancel_quorum_bps: 1,
+            cancel_voting_period_seconds: CANCEL_VOTING_PERIOD_SECONDS,
+            cancel_grace_period_seconds: CANCEL_GRACE_PERIOD_SECONDS,
+            redistribution_policy,
+            reserve_fund: env.reserve_fund.pubkey(),
+            token_mint: chrt_mint(),
+            permissionless_rewards: true,
+            crank_tip_chrt_amount: 1,
+            vesting_cliff_seconds: 0,
+            vesting_duration_seconds: 0,
+        }.data(),
+    };
+    process(&mut env.context, &[initialize], &[]).await.unwrap();
+
+    let airdrops: Vec<Instruction> = [&env.fundraising_owner, &env.beneficiary, &env.donater, &env.referrer].iter()
+        .map(|wallet| system_instruction::transfer(&service_owner, &wallet.pubkey(), 1_000_000_000))
+        .collect();
+    process(&mut env.context, &airdrops, &[]).await.unwrap();
+
+    let rent = env.context.banks_client.get_rent().await.unwrap();
+    let create_token_accounts: Vec<Instruction> = [(&env.referrer_token_account, &env.referrer), (&env.fundraising_owner_token_account, &env.fundraising_owner)].iter()
+        .flat_map(|(token_account, wallet)| [
+            system_instruction::create_account(&service_owner, &token_account.pubkey(), rent.minimum_balance(spl_token::state::Account::LEN), spl_token::state::Account::LEN as u64, &spl_token::id()),
+            spl_token::instruction::initialize_account(&spl_token::id(), &token_account.pubkey(), &chrt_mint(), &wallet.pubkey()).unwrap(),
+        ])
+        .collect();
+    process(&mut env.context, &create_token_accounts, &[&env.referrer_token_account, &env.fundraising_owner_token_account]).await.unwrap();
+
+    for fundraising_id in 0..2 {
+        create_fundraising(&mut env, fundraising_id).await;
+        let donate = donate_instruction(&env, fundraising_id);
+        process(&mut env.context, &[donate], &[&env.donater]).await.unwrap();
+    }
+
+    env
+}
+
+async fn create_fundraising(env: &mut TestEnv, fundraising_id: u64) {
+    let owner = env.fundraising_owner.pubkey();
+    let create_fundraising = Instruction {
+        program_id: solana_donation::id(),
+        accounts: solana_donation::accounts::CreateFundraising {
+            owner,
+            donation_service: state_pda(),
+            fundraising: fundraising_pda(fundraising_id),
+            organizer: Pubkey::find_program_address(&[b"organizer", owner.as_ref()], &solana_donation::id()).0,
+            system_program: system_program::id(),
+            token_program: spl_token::id(),
+        }.to_account_metas(None),
+        data: solana_donation::instruction::CreateFundraising {
+            title: "Security".to_string(),
+            metadata_uri: "https://example.com/security.json".to_string(),
+            content_hash: [0; 32],
+            category: FundraisingCategory::Other,
+            beneficiary: env.beneficiary.pubkey(),
+        }.data(),
+    };
+    process(&mut env.context, &[create_fundraising], &[&env.fundraising_owner]).await.unwrap();
+}
+
+fn donate_instruction(env: &TestEnv, fundraising_id: u64) -> Instruction {
+    let donater = env.donater.pubkey();
+    Instruction {
+        program_id: solana_donation::id(),
+        accounts: solana_donation::accounts::Donate {
+            donater,
+            donater_info: Pubkey::find_program_address(&[b"donater-info", fundraising_id.to_le_bytes().as_ref(), donater.as_ref()], &solana_donation::id()).0,
+            donater_top_info: Pubkey::find_program_address(&[b"global-top-info", donater.as_ref()], &solana_donation::id()).0,
+            donater_pending_reward: Pubkey::find_program_address(&[b"pending-reward", donater.as_ref()], &solana_donation::id()).0,
+            donation_service: state_pda(),
+            fundraising: fundraising_pda(fundraising_id),
+            chrt_mint: chrt_mint(),
+            referrer_chrt_account: env.referrer_token_account.pubkey(),
+            referrer_vesting: Pubkey::find_program_address(&[b"vesting", env.referrer.pubkey().as_ref()], &solana_donation::id()).0,
+            system_program: system_program::id(),
+            token_program: spl_token::id(),
+        }.to_account_metas(None),
+        data: solana_donation::instruction::Donate { amount: DONATED_AMOUNT, fundraising_id }.data(),
+    }
+}
+
+fn open_cancel_proposal_instruction(env: &TestEnv, fundraising_id: u64, proposal_id: u64) -> Instruction {
+    Instruction {
+        program_id: solana_donation::id(),
+        accounts: solana_donation::accounts::OpenCancelProposal {
+            proposer: env.referrer.pubkey(),
+            fundraising: fundraising_pda(fundraising_id),
+            cancel_proposal: cancel_proposal_pda(fundraising_id, proposal_id),
+            chrt_mint: chrt_mint(),
+            donation_service: state_pda(),
+            system_program: system_program::id(),
+        }.to_account_metas(None),
+        data: solana_donation::instruction::OpenCancelProposal { fundraising_id }.data(),
+    }
+}
+
+// Votes on the first proposal with the referral CHRT
+fn vote_cancel_instruction(env: &TestEnv, fundraising_id: u64, amount: u64) -> Instruction {
+    let referrer = env.referrer.pubkey();
+    let cancel_proposal = cancel_proposal_pda(fundraising_id, 0);
+    Instruction {
+        program_id: solana_donation::id(),
+        accounts: solana_donation::accounts::VoteCancelProposal {
+            voter: referrer,
+            voter_token_account: env.referrer_token_account.pubkey(),
+            chrt_mint: chrt_mint(),
+            cancel_proposal,
+            proposal_token_account: get_associated_token_address(&cancel_proposal, &chrt_mint()),
+            cancel_vote: Pubkey::find_program_address(&[b"cancel-vote", cancel_proposal.as_ref(), referrer.as_ref()], &solana_donation::id()).0,
+            fundraising: fundraising_pda(fundraising_id),
+            donation_service: state_pda(),
+            system_program: system_program::id(),
+            token_program: spl_token::id(),
+            associated_token_program: anchor_spl::associated_token::ID,
+            rent: sysvar::rent::id(),
+        }.to_account_metas(None),
+        data: solana_donation::instruction::VoteCancel { _fundraising_id: fundraising_id, _proposal_id: 0, amount }.data(),
+    }
+}
+
+// Opens a cancel proposal, votes it through with the referral CHRT and moves the clock past the grace period
+async fn pass_cancel_proposal(env: &mut TestEnv, fundraising_id: u64) {
+    let open_cancel_proposal = open_cancel_proposal_instruction(env, fundraising_id, 0);
+    let vote_cancel = vote_cancel_instruction(env, fundraising_id, DONATED_AMOUNT);
+    process(&mut env.context, &[open_cancel_proposal, vote_cancel], &[&env.referrer]).await.unwrap();
+
+    warp_clock(&mut env.context, CANCEL_VOTING_PERIOD_SECONDS + CANCEL_GRACE_PERIOD_SECONDS).await;
+}
+
+fn cancel_instruction(env: &TestEnv, fundraising_id: u64) -> Instruction {
+    Instruction {
+        program_id: solana_donation::id(),
+        accounts: solana_donation::accounts::CancelFundraising {
+            user: env.context.payer.pubkey(),
+            donation_service: state_pda(),
+            fundraising: fundraising_pda(fundraising_id),
+            cancel_proposal: cancel_proposal_pda(fundraising_id, 0),
+            reserve_fund: env.reserve_fund.pubkey(),
+            chrt_mint: chrt_mint(),
+            fundraising_token_account: get_associated_token_address(&fundraising_pda(fundraising_id), &chrt_mint()),
+            token_program: spl_token::id(),
+        }.to_account_metas(None),
+        data: solana_donation::instruction::CancelFundraising { fundraising_id, _proposal_id: 0 }.data(),
+    }
+}
+
+fn claim_redistribution_instruction(fundraising_id: u64) -> Instruction {
+    Instruction {
+        program_id: solana_donation::id(),
+        accounts: solana_donation::accounts::ClaimRedistribution {
+            donation_service: state_pda(),
+            fundraising: fundraising_pda(fundraising_id),
+        }.to_account_metas(None),
+        data: solana_donation::instruction::ClaimRedistribution { fundraising_id }.data(),
+    }
+}
+
+async fn pending_redistribution(context: &mut ProgramTestContext, fundraising_id: u64) -> u64 {
+    let donation_service: DonationService = get_state(context, state_pda()).await;
+    donation_service.active_fundraising_balances.iter().find(|x| x.id == fundraising_id).unwrap().pending_redistribution
+}
+
+// Expiry only applies to fundraisings that never received anything, so a fresh one is created for it
+async fn expire_new_fundraising(env: &mut TestEnv) -> u64 {
+    let donation_service: DonationService = get_state(&mut env.context, state_pda()).await;
+    let fundraising_id = donation_service.fundraisings_num;
+    create_fundraising(env, fundraising_id).await;
+
+    let configure_expiry = configure_expiry_instruction(env);
+    process(&mut env.context, &[configure_expiry], &[]).await.unwrap();
+    warp_clock(&mut env.context, 2).await;
+
+    let expire = expire_instruction(env, fundraising_id);
+    process(&mut env.context, &[expire], &[]).await.unwrap();
+    fundraising_id
+}
+
+fn configure_expiry_instruction(env: &TestEnv) -> Instruction {
+    Instruction {
+        program_id: solana_donation::id(),
+        accounts: solana_donation::accounts::ConfigureExpiry {
+            owner: env.context.payer.pubkey(),
+            donation_service: state_pda(),
+        }.to_account_metas(None),
+        data: solana_donation::instruction::ConfigureExpiry { expiry_period_seconds: 1 }.data(),
+    }
+}
+
+fn expire_instruction(env: &TestEnv, fundraising_id: u64) -> Instruction {
+    Instruction {
+        program_id: solana_donation::id(),
+        accounts: solana_donation::accounts::ExpireFundraising {
+            user: env.context.payer.pubkey(),
+            donation_service: state_pda(),
+            fundraising: fundraising_pda(fundraising_id),
+            owner: env.fundraising_owner.pubkey(),
+            chrt_mint: chrt_mint(),
+            fundraising_token_account: get_associated_token_address(&fundraising_pda(fundraising_id), &chrt_mint()),
+            token_program: spl_token::id(),
+        }.to_account_metas(None),
+        data: solana_donation::instruction::ExpireFundraising { fundraising_id }.data(),
+    }
+}
+
+fn withdraw_accounts(fundraising_id: u64, owner: Pubkey, authority: Pubkey, beneficiary: Pubkey) -> solana_donation::accounts::Withdraw {
+    solana_donation::accounts::Withdraw {
+        donation_service: state_pda(),
+        fundraising: fundraising_pda(fundraising_id),
+        beneficiary,
+        authority,
+        owner,
+        system_program: system_program::id(),
+        token_program: spl_token::id(),
+    }
+}
+
+fn withdraw_instruction(fundraising_id: u64, owner: Pubkey, authority: Pubkey, beneficiary: Pubkey) -> Instruction {
+    Instruction {
+        program_id: solana_donation::id(),
+        accounts: withdraw_accounts(fundraising_id, owner, authority, beneficiary).to_account_metas(None),
+        data: solana_donation::instruction::Withdraw { fundraising_id }.data(),
+    }
+}
+
+// Withdrawal reads the latest cancel proposal once any was opened
+fn with_latest_proposal(mut instruction: Instruction, fundraising_id: u64, proposal_id: u64) -> Instruction {
+    instruction.accounts.push(AccountMeta::new_readonly(cancel_proposal_pda(fundraising_id, proposal_id), false));
+    instruction
+}
+
+fn withdrawal_request_pda(fundraising_id: u64) -> Pubkey {
+    Pubkey::find_program_address(&[b"withdrawal-request", fundraising_id.to_le_bytes().as_ref()], &solana_donation::id()).0
+}
+
+fn set_withdrawal_signers_instruction(owner: Pubkey, fundraising_id: u64, signers: Vec<Pubkey>, threshold: u8) -> Instruction {
+    Instruction {
+        program_id: solana_donation::id(),
+        accounts: solana_donation::accounts::SetWithdrawalSigners {
+            owner,
+            fundraising: fundraising_pda(fundraising_id),
+            donation_service: state_pda(),
+        }.to_account_metas(None),
+        data: solana_donation::instruction::SetWithdrawalSigners {
+            _fundraising_id: fundraising_id,
+            signers,
+            threshold,
+        }.data(),
+    }
+}
+
+fn create_withdrawal_request_instruction(proposer: Pubkey, fundraising_id: u64) -> Instruction {
+    Instruction {
+        program_id: solana_donation::id(),
+        accounts: solana_donation::accounts::CreateWithdrawalRequest {
+            proposer,
+            fundraising: fundraising_pda(fundraising_id),
+            donation_service: state_pda(),
+            withdrawal_request: withdrawal_request_pda(fundraising_id),
+            system_program: system_program::id(),
+        }.to_account_metas(None),
+        data: solana_donation::instruction::CreateWithdrawalRequest { fundraising_id }.data(),
+    }
+}
+
+fn approve_withdrawal_instruction(approver: Pubkey, fundraising_id: u64) -> Instruction {
+    Instruction {
+        program_id: solana_donation::id(),
+        accounts: solana_donation::accounts::ApproveWithdrawal {
+            approver,
+            fundraising: fundraising_pda(fundraising_id),
+            donation_service: state_pda(),
+            withdrawal_request: withdrawal_request_pda(fundraising_id),
+        }.to_account_metas(None),
+        data: solana_donation::instruction::ApproveWithdrawal { fundraising_id }.data(),
+    }
+}
+
+fn set_co_organizer_instruction(owner: Pubkey, fundraising_id: u64, organizer: Pubkey, can_withdraw: bool) -> Instruction {
+    Instruction {
+        program_id: solana_donation::id(),
+        accounts: solana_donation::accounts::ManageCoOrganizers {
+            owner,
+            fundraising: fundraising_pda(fundraising_id),
+            donation_service: state_pda(),
+        }.to_account_metas(None),
+        data: solana_donation::instruction::SetCoOrganizer {
+            fundraising_id,
+            organizer,
+            can_update_metadata: true,
+            can_withdraw,
+            can_pause: true,
+        }.data(),
+    }
+}
+
+fn pause_fundraising_instruction(authority: Pubkey, fundraising_id: u64) -> Instruction {
+    Instruction {
+        program_id: solana_donation::id(),
+        accounts: solana_donation::accounts::SetFundraisingPaused {
+            authority,
+            fundraising: fundraising_pda(fundraising_id),
+            donation_service: state_pda(),
+        }.to_account_metas(None),
+        data: solana_donation::instruction::PauseFundraising { fundraising_id }.data(),
+    }
+}
+
+fn update_metadata_instruction(authority: Pubkey, fundraising_id: u64) -> Instruction {
+    Instruction {
+        program_id: solana_donation::id(),
+        accounts: solana_donation::accounts::UpdateFundraisingMetadata {
+            authority,
+            fundraising: fundraising_pda(fundraising_id),
+            donation_service: state_pda(),
+            system_program: system_program::id(),
+        }.to_account_metas(None),
+        data: solana_donation::instruction::UpdateFundraisingMetadata {
+            fundraising_id,
+            title: "Updated".to_string(),
+            metadata_uri: "https://example.com/updated.json".to_string(),
+            content_hash: [1; 32],
+            category: FundraisingCategory::Other,
+        }.data(),
+    }
+}
+
+fn propose_beneficiary_instruction(owner: Pubkey, fundraising_id: u64, new_beneficiary: Pubkey) -> Instruction {
+    Instruction {
+        program_id: solana_donation::id(),
+        accounts: solana_donation::accounts::ProposeBeneficiary {
+            owner,
+            fundraising: fundraising_pda(fundraising_id),
+            donation_service: state_pda(),
+        }.to_account_metas(None),
+        data: solana_donation::instruction::ProposeBeneficiary { fundraising_id, new_beneficiary }.data(),
+    }
+}
+
+fn accept_beneficiary_instruction(new_beneficiary: Pubkey, fundraising_id: u64) -> Instruction {
+    Instruction {
+        program_id: solana_donation::id(),
+        accounts: solana_donation::accounts::AcceptBeneficiary {
+            new_beneficiary,
+            fundraising: fundraising_pda(fundraising_id),
+            donation_service: state_pda(),
+        }.to_account_metas(None),
+        data: solana_donation::instruction::AcceptBeneficiary { fundraising_id }.data(),
+    }
+}
+
+fn donate_chrt_instruction(env: &TestEnv, fundraising_id: u64) -> Instruction {
+    Instruction {
+        program_id: solana_donation::id(),
+        accounts: solana_donation::accounts::DonateCHRT {
+            donater: env.referrer.pubkey(),
+            donater_token_account: env.referrer_token_account.pubkey(),
+            chrt_mint: chrt_mint(),
+            fundraising: fundraising_pda(fundraising_id),
+            donation_service: state_pda(),
+            system_program: system_program::id(),
+            token_program: spl_token::id(),
+        }.to_account_metas(None),
+        data: solana_donation::instruction::DonateChrt { amount: 1, _fundraising_id: fundraising_id }.data(),
+    }
+}
+
+fn chrt_stake_pda(fundraising_id: u64, staker: Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"chrt-stake", fundraising_id.to_le_bytes().as_ref(), staker.as_ref()], &solana_donation::id()).0
+}
+
+fn stake_chrt_instruction(env: &TestEnv, fundraising_id: u64) -> Instruction {
+    let staker = env.referrer.pubkey();
+    Instruction {
+        program_id: solana_donation::id(),
+        accounts: solana_donation::accounts::StakeCHRT {
+            staker,
+            staker_token_account: env.referrer_token_account.pubkey(),
+            chrt_mint: chrt_mint(),
+            fundraising_token_account: get_associated_token_address(&fundraising_pda(fundraising_id), &chrt_mint()),
+            chrt_stake: chrt_stake_pda(fundraising_id, staker),
+            fundraising: fundraising_pda(fundraising_id),
+            donation_service: state_pda(),
+            system_program: system_program::id(),
+            token_program: spl_token::id(),
+            associated_token_program: anchor_spl::associated_token::ID,
+            rent: sysvar::rent::id(),
+        }.to_account_metas(None),
+        data: solana_donation::instruction::StakeChrt { amount: 1, fundraising_id }.data(),
+    }
+}
+
+fn unstake_chrt_instruction(env: &TestEnv, fundraising_id: u64) -> Instruction {
+    let staker = env.referrer.pubkey();
+    Instruction {
+        program_id: solana_donation::id(),
+        accounts: solana_donation::accounts::UnstakeCHRT {
+            staker,
+            staker_token_account: env.referrer_token_account.pubkey(),
+            chrt_mint: chrt_mint(),
+            fundraising_token_account: get_associated_token_address(&fundraising_pda(fundraising_id), &chrt_mint()),
+            chrt_stake: chrt_stake_pda(fundraising_id, staker),
+            fundraising: fundraising_pda(fundraising_id),
+            donation_service: state_pda(),
+            token_program: spl_token::id(),
+        }.to_account_metas(None),
+        data: solana_donation::instruction::UnstakeChrt { fundraising_id }.data(),
+    }
+}
+
+fn subscription_pda(fundraising_id: u64, donater: Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"subscription", fundraising_id.to_le_bytes().as_ref(), donater.as_ref()], &solana_donation::id()).0
+}
+
+// The fundraising owner is the referrer of the subscription, so its token account receives the referral CHRT
+fn create_subscription_instruction(env: &TestEnv, fundraising_id: u64) -> Instruction {
+    let donater = env.donater.pubkey();
+    let referrer = env.fundraising_owner.pubkey();
+    Instruction {
+        program_id: solana_donation::id(),
+        accounts: solana_donation::accounts::CreateSubscription {
+            donater,
+            subscription: subscription_pda(fundraising_id, donater),
+            donater_info: Pubkey::find_program_address(&[b"donater-info", fundraising_id.to_le_bytes().as_ref(), donater.as_ref()], &solana_donation::id()).0,
+            donater_top_info: Pubkey::find_program_address(&[b"global-top-info", donater.as_ref()], &solana_donation::id()).0,
+            donater_pending_reward: Pubkey::find_program_address(&[b"pending-reward", donater.as_ref()], &solana_donation::id()).0,
+            donation_service: state_pda(),
+            fundraising: fundraising_pda(fundraising_id),
+            chrt_mint: chrt_mint(),
+            referrer_chrt_account: env.fundraising_owner_token_account.pubkey(),
+            referrer_vesting: Pubkey::find_program_address(&[b"vesting", referrer.as_ref()], &solana_donation::id()).0,
+            system_program: system_program::id(),
+        }.to_account_metas(None),
+        data: solana_donation::instruction::CreateSubscription {
+            fundraising_id,
+            amount: DONATED_AMOUNT,
+            interval_seconds: 1,
+            deposit: DONATED_AMOUNT,
+        }.data(),
+    }
+}
+
+fn execute_subscription_instruction(env: &TestEnv, fundraising_id: u64) -> Instruction {
+    let donater = env.donater.pubkey();
+    let referrer = env.fundraising_owner.pubkey();
+    Instruction {
+        program_id: solana_donation::id(),
+        accounts: solana_donation::accounts::ExecuteSubscription {
+            user: donater,
+            subscription: subscription_pda(fundraising_id, donater),
+            donater_info: Pubkey::find_program_address(&[b"donater-info", fundraising_id.to_le_bytes().as_ref(), donater.as_ref()], &solana_donation::id()).0,
+            donater_top_info: Pubkey::find_program_address(&[b"global-top-info", donater.as_ref()], &solana_donation::id()).0,
+            donation_service: state_pda(),
+            fundraising: fundraising_pda(fundraising_id),
+            chrt_mint: chrt_mint(),
+            referrer,
+            referrer_chrt_account: env.fundraising_owner_token_account.pubkey(),
+            referrer_vesting: Pubkey::find_program_address(&[b"vesting", referrer.as_ref()], &solana_donation::id()).0,
+            token_program: spl_token::id(),
+        }.to_account_metas(None),
+        data: solana_donation::instruction::ExecuteSubscription { fundraising_id }.data(),
+    }
+}
+
+// The organizer stakes from its own token account, which is enough to reach the status check
+fn appeal_cancel_proposal_instruction(env: &TestEnv, fundraising_id: u64) -> Instruction {
+    let organizer = env.fundraising_owner.pubkey();
+    let cancel_proposal = cancel_proposal_pda(fundraising_id, 0);
+    Instruction {
+        program_id: solana_donation::id(),
+        accounts: solana_donation::accounts::AppealCancelProposal {
+            organizer,
+            organizer_token_account: env.fundraising_owner_token_account.pubkey(),
+            chrt_mint: chrt_mint(),
+            cancel_proposal,
+            proposal_token_account: get_associated_token_address(&cancel_proposal, &chrt_mint()),
+            fundraising: fundraising_pda(fundraising_id),
+            donation_service: state_pda(),
+            token_program: spl_token::id(),
+        }.to_account_metas(None),
+        data: solana_donation::instruction::AppealCancelProposal { fundraising_id, proposal_id: 0, amount: 1 }.data(),
+    }
+}
+
+fn claim_refund_instruction(donater: Pubkey, fundraising_id: u64) -> Instruction {
+    Instruction {
+        program_id: solana_donation::id(),
+        accounts: solana_donation::accounts::ClaimRefund {
+            donater,
+            donater_info: Pubkey::find_program_address(&[b"donater-info", fundraising_id.to_le_bytes().as_ref(), donater.as_ref()], &solana_donation::id()).0,
+            fundraising: fundraising_pda(fundraising_id),
+        }.to_account_metas(None),
+        data: solana_donation::instruction::ClaimRefund { fundraising_id }.data(),
+    }
+}
+
+#[tokio::test]
+async fn test_non_owner_cannot_withdraw() {
+    let TestEnv { mut context, fundraising_owner, beneficiary, .. } = setup(RedistributionPolicy::Proportional).await;
+    let attacker = Keypair::new();
+
+    let result = process(&mut context, &[withdraw_instruction(0, fundraising_owner.pubkey(), attacker.pubkey(), beneficiary.pubkey())], &[&attacker]).await;
+    assert_donation_error(result, DonationError::NotFundingOwner);
+
+    let result = process(&mut context, &[withdraw_instruction(0, fundraising_owner.pubkey(), attacker.pubkey(), attacker.pubkey())], &[&attacker]).await;
+    assert_donation_error(result, DonationError::InvalidBeneficiary);
+
+    let fundraising: Fundraising = get_state(&mut context, fundraising_pda(0)).await;
+    let donation_service: DonationService = get_state(&mut context, state_pda()).await;
+    assert_eq!(fundraising.status, FundraisingStatus::Active);
+    assert_eq!(fundraising.total_sum, DONATED_SUM);
+    assert!(donation_service.active_fundraising_balances.iter().any(|x| x.id == 0));
+}
+
+#[tokio::test]
+async fn test_double_withdraw() {
+    let TestEnv { mut context, fundraising_owner, beneficiary, .. } = setup(RedistributionPolicy::Proportional).await;
+
+    let beneficiary_balance_before = context.banks_client.get_balance(beneficiary.pubkey()).await.unwrap();
+    process(&mut context, &[withdraw_instruction(0, fundraising_owner.pubkey(), fundraising_owner.pubkey(), beneficiary.pubkey())], &[&fundraising_owner]).await.unwrap();
+    let beneficiary_balance_after = context.banks_client.get_balance(beneficiary.pubkey()).await.unwrap();
+    assert_eq!(beneficiary_balance_after - beneficiary_balance_before, DONATED_SUM);
+
+    // Fresh blockhash so the second withdraw isn't deduplicated as the same transaction
+    context.get_new_latest_blockhash().await.unwrap();
+    let result = process(&mut context, &[withdraw_instruction(0, fundraising_owner.pubkey(), beneficiary.pubkey(), beneficiary.pubkey())], &[&beneficiary]).await;
+    assert_donation_error(result, DonationError::FundraisingFinished);
+}
+
+#[tokio::test]
+async fn test_withdraw_after_cancel() {
+    let mut env = setup(RedistributionPolicy::Proportional).await;
+
+    pass_cancel_proposal(&mut env, 0).await;
+
+    let cancel = cancel_instruction(&env, 0);
+    let recipient_balance_before = env.context.banks_client.get_balance(fundraising_pda(1)).await.unwrap();
+    process(&mut env.context, &[cancel], &[]).await.unwrap();
+
+    // The whole net balance is assigned to the only other active fundraising, which pulls it later
+    assert_eq!(env.context.banks_client.get_balance(fundraising_pda(1)).await.unwrap(), recipient_balance_before);
+    assert_eq!(pending_redistribution(&mut env.context, 1).await, DONATED_SUM);
+
+    process(&mut env.context, &[claim_redistribution_instruction(1)], &[]).await.unwrap();
+
+    let recipient_balance_after = env.context.banks_client.get_balance(fundraising_pda(1)).await.unwrap();
+    let recipient: Fundraising = get_state(&mut env.context, fundraising_pda(1)).await;
+    assert_eq!(recipient_balance_after - recipient_balance_before, DONATED_SUM);
+    assert_eq!(recipient.total_sum, 2 * DONATED_SUM);
+    assert_eq!(pending_redistribution(&mut env.context, 1).await, 0);
+
+    env.context.get_new_latest_blockhash().await.unwrap();
+    let result = process(&mut env.context, &[claim_redistribution_instruction(1)], &[]).await;
+    assert_donation_error(result, DonationError::NothingToRedistribute);
+
+    let TestEnv { mut context, fundraising_owner, beneficiary, .. } = env;
+    let beneficiary_balance_before = context.banks_client.get_balance(beneficiary.pubkey()).await.unwrap();
+    let result = process(&mut context, &[withdraw_instruction(0, fundraising_owner.pubkey(), fundraising_owner.pubkey(), beneficiary.pubkey())], &[&fundraising_owner]).await;
+    assert_donation_error(result, DonationError::FundraisingFinished);
+    assert_eq!(context.banks_client.get_balance(beneficiary.pubkey()).await.unwrap(), beneficiary_balance_before);
+
+    let fundraising: Fundraising = get_state(&mut context, fundraising_pda(0)).await;
+    assert_eq!(fundraising.status, FundraisingStatus::Canceled);
+    assert_eq!(fundraising.total_sum, 0);
+}
+
+#[tokio::test]
+async fn test_withdraw_during_cancel_vote() {
+    let mut env = setup(RedistributionPolicy::Proportional).await;
+    let owner = env.fundraising_owner.pubkey();
+    let beneficiary = env.beneficiary.pubkey();
+
+    let open_cancel_proposal = open_cancel_proposal_instruction(&env, 0, 0);
+    let vote_cancel = vote_cancel_instruction(&env, 0, DONATED_AMOUNT);
+    process(&mut env.context, &[open_cancel_proposal, vote_cancel], &[&env.referrer]).await.unwrap();
+
+    let beneficiary_balance_before = env.context.banks_client.get_balance(beneficiary).await.unwrap();
+    let result = process(&mut env.context, &[with_latest_proposal(withdraw_instruction(0, owner, owner, beneficiary), 0, 0)], &[&env.fundraising_owner]).await;
+    assert_donation_error(result, DonationError::VotingInProgress);
+
+    // Still blocked in the grace period, when only the appeal can change the outcome
+    warp_clock(&mut env.context, CANCEL_VOTING_PERIOD_SECONDS).await;
+    env.context.get_new_latest_blockhash().await.unwrap();
+    let result = process(&mut env.context, &[with_latest_proposal(withdraw_instruction(0, owner, owner, beneficiary), 0, 0)], &[&env.fundraising_owner]).await;
+    assert_donation_error(result, DonationError::VotingInProgress);
+
+    // And once the proposal passed, until it is executed
+    warp_clock(&mut env.context, CANCEL_GRACE_PERIOD_SECONDS).await;
+    let result = process(&mut env.context, &[withdraw_instruction(0, owner, owner, beneficiary)], &[&env.fundraising_owner]).await;
+    assert_donation_error(result, DonationError::InvalidPreviousProposal);
+    env.context.get_new_latest_blockhash().await.unwrap();
+    let result = process(&mut env.context, &[with_latest_proposal(withdraw_instruction(0, owner, owner, beneficiary), 0, 0)], &[&env.fundraising_owner]).await;
+    assert_donation_error(result, DonationError::PassedProposalPending);
+
+    assert_eq!(env.context.banks_client.get_balance(beneficiary).await.unwrap(), beneficiary_balance_before);
+    let fundraising: Fundraising = get_state(&mut env.context, fundraising_pda(0)).await;
+    assert_eq!(fundraising.status, FundraisingStatus::Active);
+    assert_eq!(fundraising.total_sum, DONATED_SUM);
+}
+
+#[tokio::test]
+async fn test_expire_after_rejected_cancel_vote() {
+    let mut env = setup(RedistributionPolicy::EqualSplit).await;
+
+    // Fundraising 2 never receives a donation but gets a share of the canceled one
+    create_fundraising(&mut env, 2).await;
+    pass_cancel_proposal(&mut env, 0).await;
+    let cancel = cancel_instruction(&env, 0);
+    process(&mut env.context, &[cancel], &[]).await.unwrap();
+    assert_eq!(pending_redistribution(&mut env.context, 2).await, DONATED_SUM / 2);
+
+    let open_cancel_proposal = open_cancel_proposal_instruction(&env, 2, 0);
+    let configure_expiry = configure_expiry_instruction(&env);
+    process(&mut env.context, &[open_cancel_proposal, configure_expiry], &[&env.referrer]).await.unwrap();
+    warp_clock(&mut env.context, 2).await;
+
+    let expire = with_latest_proposal(expire_instruction(&env, 2), 2, 0);
+    let result = process(&mut env.context, &[expire.clone()], &[]).await;
+    assert_donation_error(result, DonationError::VotingInProgress);
+
+    // A proposal without votes doesn't keep the fundraising alive once its voting is over
+    warp_clock(&mut env.context, CANCEL_VOTING_PERIOD_SECONDS + CANCEL_GRACE_PERIOD_SECONDS).await;
+    let expire_without_proposal = expire_instruction(&env, 2);
+    let result = process(&mut env.context, &[expire_without_proposal], &[]).await;
+    assert_donation_error(result, DonationError::InvalidPreviousProposal);
+
+    let donation_service: DonationService = get_state(&mut env.context, state_pda()).await;
+    let total_fee_before = donation_service.total_fee;
+    env.context.get_new_latest_blockhash().await.unwrap();
+    process(&mut env.context, &[expire], &[]).await.unwrap();
+
+    // The unclaimed share is forfeited to the platform instead of staying in the service account
+    let donation_service: DonationService = get_state(&mut env.context, state_pda()).await;
+    assert_eq!(donation_service.total_fee, total_fee_before + DONATED_SUM / 2);
+    assert!(donation_service.active_fundraising_balances.iter().all(|x| x.id != 2));
+    assert!(env.context.banks_client.get_account(fundraising_pda(2)).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_pause_restarts_expiry_period() {
+    let mut env = setup(RedistributionPolicy::Proportional).await;
+    let owner = env.fundraising_owner.pubkey();
+
+    create_fundraising(&mut env, 2).await;
+    let configure_expiry = configure_expiry_instruction(&env);
+    process(&mut env.context, &[configure_expiry], &[]).await.unwrap();
+    warp_clock(&mut env.context, 2).await;
+
+    // Pausing is the organizer's activity, it doesn't hand the stale fundraising over to expiry
+    let pause_fundraising = pause_fundraising_instruction(owner, 2);
+    process(&mut env.context, &[pause_fundraising], &[&env.fundraising_owner]).await.unwrap();
+    let expire = expire_instruction(&env, 2);
+    let result = process(&mut env.context, &[expire.clone()], &[]).await;
+    assert_donation_error(result, DonationError::FundraisingNotStale);
+
+    warp_clock(&mut env.context, 2).await;
+    env.context.get_new_latest_blockhash().await.unwrap();
+    process(&mut env.context, &[expire], &[]).await.unwrap();
+    assert!(env.context.banks_client.get_account(fundraising_pda(2)).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_cancel_equal_split() {
+    let mut env = setup(RedistributionPolicy::EqualSplit).await;
+
+    // Fundraising 2 holds twice the balance of fundraising 1 but gets the same share
+    create_fundraising(&mut env, 2).await;
+    let donate = donate_instruction(&env, 2);
+    process(&mut env.context, &[donate.clone(), donate], &[&env.donater]).await.unwrap();
+
+    pass_cancel_proposal(&mut env, 0).await;
+
+    let balances_before = [
+        env.context.banks_client.get_balance(fundraising_pda(1)).await.unwrap(),
+        env.context.banks_client.get_balance(fundraising_pda(2)).await.unwrap(),
+    ];
+    let cancel = cancel_instruction(&env, 0);
+    process(&mut env.context, &[cancel], &[]).await.unwrap();
+    assert_eq!(pending_redistribution(&mut env.context, 1).await, DONATED_SUM / 2);
+    assert_eq!(pending_redistribution(&mut env.context, 2).await, DONATED_SUM / 2);
+
+    process(&mut env.context, &[claim_redistribution_instruction(1)], &[]).await.unwrap();
+    let recipient: Fundraising = get_state(&mut env.context, fundraising_pda(1)).await;
+    assert_eq!(env.context.banks_client.get_balance(fundraising_pda(1)).await.unwrap() - balances_before[0], DONATED_SUM / 2);
+    assert_eq!(recipient.total_sum, DONATED_SUM + DONATED_SUM / 2);
+
+    // An unclaimed share is paid out together with the withdrawal
+    let owner = env.fundraising_owner.pubkey();
+    let beneficiary = env.beneficiary.pubkey();
+    let beneficiary_balance_before = env.context.banks_client.get_balance(beneficiary).await.unwrap();
+    process(&mut env.context, &[withdraw_instruction(2, owner, owner, beneficiary)], &[&env.fundraising_owner]).await.unwrap();
+    assert_eq!(env.context.banks_client.get_balance(beneficiary).await.unwrap() - beneficiary_balance_before, 2 * DONATED_SUM + DONATED_SUM / 2);
+    assert_eq!(env.context.banks_client.get_balance(fundraising_pda(2)).await.unwrap(), balances_before[1] - 2 * DONATED_SUM);
+
+    assert_eq!(env.context.banks_client.get_balance(env.reserve_fund.pubkey()).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_cancel_refund_donaters() {
+    let mut env = setup(RedistributionPolicy::RefundDonaters).await;
+
+    pass_cancel_proposal(&mut env, 0).await;
+
+    let fundraising_balance_before = env.context.banks_client.get_balance(fundraising_pda(0)).await.unwrap();
+    let recipient_balance_before = env.context.banks_client.get_balance(fundraising_pda(1)).await.unwrap();
+    let cancel = cancel_instruction(&env, 0);
+    process(&mut env.context, &[cancel], &[]).await.unwrap();
+
+    // Nothing leaves the fundraising until the donaters claim it
+    assert_eq!(env.context.banks_client.get_balance(fundraising_pda(0)).await.unwrap(), fundraising_balance_before);
+    assert_eq!(env.context.banks_client.get_balance(fundraising_pda(1)).await.unwrap(), recipient_balance_before);
+    let fundraising: Fundraising = get_state(&mut env.context, fundraising_pda(0)).await;
+    assert_eq!(fundraising.refund_pool, DONATED_SUM);
+
+    let donater = env.donater.pubkey();
+    let donater_balance_before = env.context.banks_client.get_balance(donater).await.unwrap();
+    process(&mut env.context, &[claim_refund_instruction(donater, 0)], &[&env.donater]).await.unwrap();
+
+    // The only donater gets the whole net sum back
+    assert_eq!(env.context.banks_client.get_balance(donater).await.unwrap() - donater_balance_before, DONATED_SUM);
+    assert_eq!(fundraising_balance_before - env.context.banks_client.get_balance(fundraising_pda(0)).await.unwrap(), DONATED_SUM);
+    let fundraising: Fundraising = get_state(&mut env.context, fundraising_pda(0)).await;
+    assert_eq!(fundraising.total_sum, 0);
+
+    env.context.get_new_latest_blockhash().await.unwrap();
+    let result = process(&mut env.context, &[claim_refund_instruction(donater, 0)], &[&env.donater]).await;
+    assert_donation_error(result, DonationError::AlreadyRefunded);
+}
+
+#[tokio::test]
+async fn test_cancel_reserve_fund() {
+    let mut env = setup(RedistributionPolicy::ReserveFund).await;
+
+    pass_cancel_proposal(&mut env, 0).await;
+
+    let recipient_balance_before = env.context.banks_client.get_balance(fundraising_pda(1)).await.unwrap();
+    let cancel = cancel_instruction(&env, 0);
+    process(&mut env.context, &[cancel], &[]).await.unwrap();
+
+    assert_eq!(env.context.banks_client.get_balance(env.reserve_fund.pubkey()).await.unwrap(), DONATED_SUM);
+    assert_eq!(env.context.banks_client.get_balance(fundraising_pda(1)).await.unwrap(), recipient_balance_before);
+    let fundraising: Fundraising = get_state(&mut env.context, fundraising_pda(0)).await;
+    let recipient: Fundraising = get_state(&mut env.context, fundraising_pda(1)).await;
+    assert_eq!(fundraising.total_sum, 0);
+    assert_eq!(recipient.total_sum, DONATED_SUM);
+}
+
+#[tokio::test]
+async fn test_cancel_without_active_balance_falls_back_to_reserve_fund() {
+    let mut env = setup(RedistributionPolicy::Proportional).await;
+
+    // Leaves fundraising 2 as the only active one, with nothing to weigh the split by
+    let owner = env.fundraising_owner.pubkey();
+    let beneficiary = env.beneficiary.pubkey();
+    process(&mut env.context, &[withdraw_instruction(1, owner, owner, beneficiary)], &[&env.fundraising_owner]).await.unwrap();
+    create_fundraising(&mut env, 2).await;
+
+    pass_cancel_proposal(&mut env, 0).await;
+
+    let recipient_balance_before = env.context.banks_client.get_balance(fundraising_pda(2)).await.unwrap();
+    let cancel = cancel_instruction(&env, 0);
+    process(&mut env.context, &[cancel], &[]).await.unwrap();
+
+    assert_eq!(env.context.banks_client.get_balance(env.reserve_fund.pubkey()).await.unwrap(), DONATED_SUM);
+    assert_eq!(env.context.banks_client.get_balance(fundraising_pda(2)).await.unwrap(), recipient_balance_before);
+    let recipient: Fundraising = get_state(&mut env.context, fundraising_pda(2)).await;
+    assert_eq!(recipient.total_sum, 0);
+}
+
+#[tokio::test]
+async fn test_withdraw_status_matrix() {
+    let statuses = [
+        FundraisingStatus::Active,
+        FundraisingStatus::Withdrawn,
+        FundraisingStatus::Canceled,
+        FundraisingStatus::Expired,
+    ];
+
+    for status in statuses {
+        let mut env = setup(RedistributionPolicy::Proportional).await;
+        let owner = env.fundraising_owner.pubkey();
+        let beneficiary = env.beneficiary.pubkey();
+
+        let fundraising_id = match status {
+            FundraisingStatus::Active => 0,
+            FundraisingStatus::Withdrawn => {
+                process(&mut env.context, &[withdraw_instruction(0, owner, owner, beneficiary)], &[&env.fundraising_owner]).await.unwrap();
+                0
+            }
+            FundraisingStatus::Canceled => {
+                pass_cancel_proposal(&mut env, 0).await;
+                let cancel = cancel_instruction(&env, 0);
+                process(&mut env.context, &[cancel], &[]).await.unwrap();
+                0
+            }
+            FundraisingStatus::Expired => expire_new_fundraising(&mut env).await,
+        };
+
+        env.context.get_new_latest_blockhash().await.unwrap();
+        let beneficiary_balance_before = env.context.banks_client.get_balance(beneficiary).await.unwrap();
+        let result = process(&mut env.context, &[withdraw_instruction(fundraising_id, owner, owner, beneficiary)], &[&env.fundraising_owner]).await;
+        let beneficiary_balance_after = env.context.banks_client.get_balance(beneficiary).await.unwrap();
+
+        match status {
+            FundraisingStatus::Active => {
+                result.unwrap();
+                let fundraising: Fundraising = get_state(&mut env.context, fundraising_pda(fundraising_id)).await;
+                assert_eq!(fundraising.status, FundraisingStatus::Withdrawn);
+                assert_eq!(beneficiary_balance_after - beneficiary_balance_before, DONATED_SUM);
+            }
+            // Expired fundraisings are closed right away
+            FundraisingStatus::Expired => {
+                assert_anchor_error(result, ErrorCode::AccountNotInitialized);
+                assert!(env.context.banks_client.get_account(fundraising_pda(fundraising_id)).await.unwrap().is_none());
+            }
+            _ => {
+                assert_donation_error(result, DonationError::FundraisingFinished);
+                let fundraising: Fundraising = get_state(&mut env.context, fundraising_pda(fundraising_id)).await;
+                assert_eq!(fundraising.status, status);
+                assert_eq!(beneficiary_balance_after, beneficiary_balance_before);
+            }
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_finished_fundraising_matrix() {
+    let statuses = [
+        FundraisingStatus::Withdrawn,
+        FundraisingStatus::Canceled,
+        FundraisingStatus::Expired,
+    ];
+
+    for status in statuses {
+        let mut env = setup(RedistributionPolicy::Proportional).await;
+        let owner = env.fundraising_owner.pubkey();
+        let beneficiary = env.beneficiary.pubkey();
+
+        // Fundraising 0 gets the state its instructions operate on while still active,
+        // fundraising 1 is left untouched for the instructions that create that state
+        let (prepared_id, untouched_id) = match status {
+            FundraisingStatus::Expired => {
+                let fundraising_id = expire_new_fundraising(&mut env).await;
+                (fundraising_id, fundraising_id)
+            }
+            _ => {
+                let prepare = [
+                    set_withdrawal_signers_instruction(owner, 0, vec![owner, beneficiary], 0),
+                    set_withdrawal_signers_instruction(owner, 1, vec![owner, beneficiary], 0),
+                    create_withdrawal_request_instruction(owner, 0),
+                    propose_beneficiary_instruction(owner, 0, env.donater.pubkey()),
+                    configure_expiry_instruction(&env),
+                ];
+                process(&mut env.context, &prepare, &[&env.fundraising_owner]).await.unwrap();
+                let create_subscription = create_subscription_instruction(&env, 0);
+                process(&mut env.context, &[create_subscription], &[&env.donater]).await.unwrap();
+                let stake = stake_chrt_instruction(&env, 0);
+                process(&mut env.context, &[stake], &[&env.referrer]).await.unwrap();
+
+                let unstake = unstake_chrt_instruction(&env, 0);
+                let result = process(&mut env.context, &[unstake], &[&env.referrer]).await;
+                assert_donation_error(result, DonationError::FundraisingActive);
+
+                if status == FundraisingStatus::Withdrawn {
+                    // Nobody votes, so the proposals fail and don't hold the withdrawal back
+                    let open_cancel_proposals = [open_cancel_proposal_instruction(&env, 0, 0), open_cancel_proposal_instruction(&env, 1, 0)];
+                    process(&mut env.context, &open_cancel_proposals, &[&env.referrer]).await.unwrap();
+                    warp_clock(&mut env.context, CANCEL_VOTING_PERIOD_SECONDS + CANCEL_GRACE_PERIOD_SECONDS).await;
+
+                    let withdraw = [
+                        with_latest_proposal(withdraw_instruction(0, owner, owner, beneficiary), 0, 0),
+                        with_latest_proposal(withdraw_instruction(1, owner, owner, beneficiary), 1, 0),
+                    ];
+                    process(&mut env.context, &withdraw, &[&env.fundraising_owner]).await.unwrap();
+                } else {
+                    pass_cancel_proposal(&mut env, 0).await;
+                    pass_cancel_proposal(&mut env, 1).await;
+                    let cancel = [cancel_instruction(&env, 0), cancel_instruction(&env, 1)];
+                    process(&mut env.context, &cancel, &[]).await.unwrap();
+                }
+                (0, 1)
+            }
+        };
+
+        // Fresh blockhash so repeating a preparation step isn't deduplicated as the same transaction
+        env.context.get_new_latest_blockhash().await.unwrap();
+        let cases = [
+            ("update_fundraising_metadata", update_metadata_instruction(owner, prepared_id), vec![&env.fundraising_owner]),
+            ("pause_fundraising", pause_fundraising_instruction(owner, prepared_id), vec![&env.fundraising_owner]),
+            ("set_co_organizer", set_co_organizer_instruction(owner, prepared_id, env.referrer.pubkey(), true), vec![&env.fundraising_owner]),
+            ("propose_beneficiary", propose_beneficiary_instruction(owner, prepared_id, env.referrer.pubkey()), vec![&env.fundraising_owner]),
+            ("accept_beneficiary", accept_beneficiary_instruction(env.donater.pubkey(), prepared_id), vec![&env.donater]),
+            ("donate", donate_instruction(&env, prepared_id), vec![&env.donater]),
+            ("donate_chrt", donate_chrt_instruction(&env, prepared_id), vec![&env.referrer]),
+            ("create_subscription", create_subscription_instruction(&env, untouched_id), vec![&env.donater]),
+            ("execute_subscription", execute_subscription_instruction(&env, prepared_id), vec![&env.donater]),
+            ("stake_chrt", stake_chrt_instruction(&env, prepared_id), vec![&env.referrer]),
+            ("open_cancel_proposal", open_cancel_proposal_instruction(&env, prepared_id, 1), vec![&env.referrer]),
+            ("vote_cancel", vote_cancel_instruction(&env, prepared_id, 1), vec![&env.referrer]),
+            ("appeal_cancel_proposal", appeal_cancel_proposal_instruction(&env, prepared_id), vec![&env.fundraising_owner]),
+            ("set_withdrawal_signers", set_withdrawal_signers_instruction(owner, prepared_id, vec![owner], 1), vec![&env.fundraising_owner]),
+            ("create_withdrawal_request", create_withdrawal_request_instruction(owner, untouched_id), vec![&env.fundraising_owner]),
+            ("approve_withdrawal", approve_withdrawal_instruction(beneficiary, prepared_id), vec![&env.beneficiary]),
+            ("cancel_fundraising", cancel_instruction(&env, prepared_id), vec![]),
+            ("expire_fundraising", expire_instruction(&env, prepared_id), vec![]),
+            ("withdraw", withdraw_instruction(prepared_id, owner, owner, beneficiary), vec![&env.fundraising_owner]),
+        ];
+
+        // Expired fundraisings are closed, so nothing can even load them
+        let (code, error) = match status {
+            FundraisingStatus::Expired => (u32::from(ErrorCode::AccountNotInitialized), ErrorCode::AccountNotInitialized.to_string()),
+            _ => (u32::from(DonationError::FundraisingFinished), DonationError::FundraisingFinished.to_string()),
+        };
+        for (name, instruction, signers) in cases {
+            let result = process(&mut env.context, &[instruction], &signers).await;
+            assert_custom_error(result, code, format!("{} ({} on a {:?} fundraising)", error, name, status));
+        }
+
+        if status != FundraisingStatus::Expired {
+            let fundraising: Fundraising = get_state(&mut env.context, fundraising_pda(prepared_id)).await;
+            assert_eq!(fundraising.status, status);
+            assert_eq!(fundraising.pending_beneficiary, Some(env.donater.pubkey()));
+
+            // Stakes are only returned once the fundraising is finished
+            let staker_balance_before = get_state::<TokenAccount>(&mut env.context, env.referrer_token_account.pubkey()).await.amount;
+            let unstake = unstake_chrt_instruction(&env, prepared_id);
+            env.context.get_new_latest_blockhash().await.unwrap();
+            process(&mut env.context, &[unstake], &[&env.referrer]).await.unwrap();
+            let staker_balance_after = get_state::<TokenAccount>(&mut env.context, env.referrer_token_account.pubkey()).await.amount;
+            assert_eq!(staker_balance_after - staker_balance_before, 1);
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_co_organizer_withdraw_role() {
+    let TestEnv { mut context, fundraising_owner, beneficiary, .. } = setup(RedistributionPolicy::Proportional).await;
+    let co_organizer = Keypair::new();
+
+    let set_co_organizer = |can_withdraw: bool| set_co_organizer_instruction(fundraising_owner.pubkey(), 0, co_organizer.pubkey(), can_withdraw);
+
+    process(&mut context, &[set_co_organizer(false)], &[&fundraising_owner]).await.unwrap();
+    let result = process(&mut context, &[withdraw_instruction(0, fundraising_owner.pubkey(), co_organizer.pubkey(), beneficiary.pubkey())], &[&co_organizer]).await;
+    assert_donation_error(result, DonationError::NotFundingOwner);
+
+    process(&mut context, &[set_co_organizer(true)], &[&fundraising_owner]).await.unwrap();
+    context.get_new_latest_blockhash().await.unwrap();
+    process(&mut context, &[withdraw_instruction(0, fundraising_owner.pubkey(), co_organizer.pubkey(), beneficiary.pubkey())], &[&co_organizer]).await.unwrap();
+
+    let fundraising: Fundraising = get_state(&mut context, fundraising_pda(0)).await;
+    assert_eq!(fundraising.status, FundraisingStatus::Withdrawn);
+}
+
+#[tokio::test]
+async fn test_multisig_withdrawal() {
+    let TestEnv { mut context, fundraising_owner, beneficiary, .. } = setup(RedistributionPolicy::Proportional).await;
+    let set_withdrawal_signers = |signers: Vec<Pubkey>, threshold: u8| set_withdrawal_signers_instruction(fundraising_owner.pubkey(), 0, signers, threshold);
+
+    process(&mut context, &[set_withdrawal_signers(vec![fundraising_owner.pubkey(), beneficiary.pubkey()], 2)], &[&fundraising_owner]).await.unwrap();
+
+    // The owner can't lower the threshold to skip the other signers
+    let result = process(&mut context, &[set_withdrawal_signers(vec![fundraising_owner.pubkey()], 1)], &[&fundraising_owner]).await;
+    assert_donation_error(result, DonationError::WithdrawalSignersLocked);
+
+    process(&mut context, &[create_withdrawal_request_instruction(fundraising_owner.pubkey(), 0)], &[&fundraising_owner]).await.unwrap();
+
+    let execute_withdrawal = || Instruction {
+        program_id: solana_donation::id(),
+        accounts: solana_donation::accounts::ExecuteWithdrawal {
+            withdraw: withdraw_accounts(0, fundraising_owner.pubkey(), fundraising_owner.pubkey(), beneficiary.pubkey()),
+            withdrawal_request: withdrawal_request_pda(0),
+        }.to_account_metas(None),
+        data: solana_donation::instruction::ExecuteWithdrawal { fundraising_id: 0 }.data(),
+    };
+
+    let result = process(&mut context, &[withdraw_instruction(0, fundraising_owner.pubkey(), fundraising_owner.pubkey(), beneficiary.pubkey())], &[&fundraising_owner]).await;
+    assert_donation_error(result, DonationError::WithdrawalApprovalRequired);
+
+    let result = process(&mut context, &[execute_withdrawal()], &[&fundraising_owner]).await;
+    assert_donation_error(result, DonationError::NotEnoughApprovals);
+
+    process(&mut context, &[approve_withdrawal_instruction(beneficiary.pubkey(), 0)], &[&beneficiary]).await.unwrap();
+
+    context.get_new_latest_blockhash().await.unwrap();
+    let beneficiary_balance_before = context.banks_client.get_balance(beneficiary.pubkey()).await.unwrap();
+    process(&mut context, &[execute_withdrawal()], &[&fundraising_owner]).await.unwrap();
+    let beneficiary_balance_after = context.banks_client.get_balance(beneficiary.pubkey()).await.unwrap();
+    assert_eq!(beneficiary_balance_after - beneficiary_balance_before, DONATED_SUM);
+
+    let fundraising: Fundraising = get_state(&mut context, fundraising_pda(0)).await;
+    assert_eq!(fundraising.status, FundraisingStatus::Withdrawn);
+}
+
+#[tokio::test]
+async fn test_withdraw_while_paused() {
+    let TestEnv { mut context, fundraising_owner, beneficiary, .. } = setup(RedistributionPolicy::Proportional).await;
+
+    let pause = Instruction {
+        program_id: solana_donation::id(),
+        accounts: solana_donation::accounts::SetPaused {
+            authority: context.payer.pubkey(),
+            donation_service: state_pda(),
+        }.to_account_metas(None),
+        data: solana_donation::instruction::Pause {}.data(),
+    };
+    process(&mut context, &[pause], &[]).await.unwrap();
+
+    let donation_service: DonationService = get_state(&mut context, state_pda()).await;
+    assert!(donation_service.paused);
+
+    process(&mut context, &[withdraw_instruction(0, fundraising_owner.pubkey(), fundraising_owner.pubkey(), beneficiary.pubkey())], &[&fundraising_owner]).await.unwrap();
+
+    let fundraising: Fundraising = get_state(&mut context, fundraising_pda(0)).await;
+    assert_eq!(fundraising.status, FundraisingStatus::Withdrawn);
+}
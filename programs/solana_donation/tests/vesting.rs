@@ -0,0 +1,96 @@
+use anchor_lang::prelude::Pubkey;
+use solana_donation::VestingAccount;
+
+const CLIFF_SECONDS: u64 = 10;
+const DURATION_SECONDS: u64 = 100;
+
+fn vesting_account() -> VestingAccount {
+    VestingAccount {
+        owner: Pubkey::default(),
+        tranches: vec![],
+        vested_carry: 0,
+        total_released: 0,
+        bump: 0,
+    }
+}
+
+fn releasable(vesting: &VestingAccount, current_time: u64) -> u64 {
+    vesting.releasable(CLIFF_SECONDS, DURATION_SECONDS, current_time).unwrap()
+}
+
+#[test]
+fn nothing_is_released_before_the_cliff() {
+    let mut vesting = vesting_account();
+    vesting.credit(1_000, CLIFF_SECONDS, DURATION_SECONDS, 100).unwrap();
+
+    assert_eq!(releasable(&vesting, 100), 0);
+    assert_eq!(releasable(&vesting, 109), 0);
+    assert_eq!(releasable(&vesting, 110), 100);
+}
+
+#[test]
+fn grant_is_released_linearly() {
+    let mut vesting = vesting_account();
+    vesting.credit(1_000, CLIFF_SECONDS, DURATION_SECONDS, 100).unwrap();
+
+    assert_eq!(releasable(&vesting, 125), 250);
+    assert_eq!(releasable(&vesting, 150), 500);
+    assert_eq!(releasable(&vesting, 199), 990);
+    assert_eq!(releasable(&vesting, 200), 1_000);
+    assert_eq!(releasable(&vesting, 1_000), 1_000);
+}
+
+#[test]
+fn credit_mid_schedule_keeps_the_earlier_grant_on_its_schedule() {
+    let mut vesting = vesting_account();
+    vesting.credit(1_000, CLIFF_SECONDS, DURATION_SECONDS, 0).unwrap();
+    vesting.credit(1_000, CLIFF_SECONDS, DURATION_SECONDS, 50).unwrap();
+
+    // The new grant is still in its own cliff
+    assert_eq!(releasable(&vesting, 55), 550);
+    // The first grant is fully vested on time, the second one is halfway through
+    assert_eq!(releasable(&vesting, 100), 1_500);
+    assert_eq!(releasable(&vesting, 150), 2_000);
+}
+
+#[test]
+fn released_amount_is_not_released_again() {
+    let mut vesting = vesting_account();
+    vesting.credit(1_000, CLIFF_SECONDS, DURATION_SECONDS, 0).unwrap();
+
+    vesting.mark_released(CLIFF_SECONDS, DURATION_SECONDS, 50);
+    assert_eq!(releasable(&vesting, 50), 0);
+    assert_eq!(releasable(&vesting, 75), 250);
+
+    vesting.credit(1_000, CLIFF_SECONDS, DURATION_SECONDS, 75).unwrap();
+    assert_eq!(releasable(&vesting, 100), 500 + 250);
+
+    vesting.mark_released(CLIFF_SECONDS, DURATION_SECONDS, 100);
+    assert_eq!(vesting.tranches.len(), 1);
+    assert_eq!(releasable(&vesting, 175), 750);
+}
+
+#[test]
+fn fully_vested_tranches_are_carried_over() {
+    let mut vesting = vesting_account();
+    vesting.credit(1_000, CLIFF_SECONDS, DURATION_SECONDS, 0).unwrap();
+    vesting.mark_released(CLIFF_SECONDS, DURATION_SECONDS, 40);
+
+    vesting.credit(500, CLIFF_SECONDS, DURATION_SECONDS, 200).unwrap();
+
+    assert_eq!(vesting.tranches.len(), 1);
+    assert_eq!(vesting.vested_carry, 600);
+    assert_eq!(releasable(&vesting, 200), 600);
+    assert_eq!(releasable(&vesting, 250), 850);
+}
+
+#[test]
+fn close_grants_share_a_tranche() {
+    let mut vesting = vesting_account();
+    for current_time in 0..1_000 {
+        vesting.credit(1, CLIFF_SECONDS, DURATION_SECONDS, current_time).unwrap();
+        assert!(vesting.tranches.len() <= 16);
+    }
+
+    assert_eq!(releasable(&vesting, 2_000), 1_000);
+}
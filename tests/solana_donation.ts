@@ -3,7 +3,8 @@ import { Program, web3 } from "@project-serum/anchor";
 import { BN } from "bn.js";
 import { assert } from "chai";
 import { SolanaDonation } from "../target/types/solana_donation";
-import { createMint, getAccount, getOrCreateAssociatedTokenAccount, mintTo, TOKEN_PROGRAM_ID } from '@solana/spl-token';
+import { createHash } from "crypto";
+import { createAccount, createMint, getAccount, getAssociatedTokenAddress, getMint, getOrCreateAssociatedTokenAccount, mintTo, transfer, TOKEN_PROGRAM_ID } from '@solana/spl-token';
 
 describe("solana_donation", () => {
 
@@ -14,6 +15,7 @@ describe("solana_donation", () => {
   const owner = (program.provider as anchor.AnchorProvider).wallet;
 
   const fundraisingOwnerAccount = web3.Keypair.generate();
+  const beneficiary = web3.Keypair.generate();
   const payer = web3.Keypair.generate();
 
   const donater = web3.Keypair.generate();
@@ -28,7 +30,14 @@ describe("solana_donation", () => {
   const ownerFeePercent = new BN(1);
   const rewardChrtAmount = new BN(2);
   const noFeeChrtThreshold = new BN(1);
-  const cancelChrtThreshold = new BN(1);
+  const cancelQuorumBps = new BN(1);
+  const cancelVotingPeriodSeconds = new BN(3);
+  const cancelGracePeriodSeconds = new BN(5);
+  const redistributionPolicy = { proportional: {} };
+  const permissionlessRewards = true;
+  const crankTipChrtAmount = new BN(1);
+  const vestingCliffSeconds = new BN(0);
+  const vestingDurationSeconds = new BN(0);
   const sumToDonate = new anchor.BN(1000);
 
   const user1 = web3.Keypair.generate();
@@ -47,6 +56,19 @@ describe("solana_donation", () => {
   const fundraisingId4 = new BN(3);
   const fundraisingId5 = new BN(4);
 
+  // Every other active fundraising gets a share of a canceled balance and claims it on its own
+  const redistributionRecipients = async (canceledFundraisingId: BN) => {
+    const [statePda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("state")], program.programId);
+    const donationState = await program.account.donationService.fetch(statePda);
+
+    const recipients = [];
+    for (const activeBalance of donationState.activeFundraisingBalances.filter(x => !x.id.eq(canceledFundraisingId))) {
+      const [fundraisingPda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("fundraising"), activeBalance.id.toBuffer('le', 8)], program.programId);
+      recipients.push({ id: activeBalance.id, pubkey: fundraisingPda });
+    }
+    return recipients;
+  };
+
   it("Test initialization", async () => {
     await provider.connection.confirmTransaction(await provider.connection.requestAirdrop(payer.publicKey, 1 * anchor.web3.LAMPORTS_PER_SOL));
 
@@ -54,7 +76,7 @@ describe("solana_donation", () => {
 
     chrtMint = await createMint(provider.connection, payer, statePda, null, 3);
 
-    await program.methods.initialize(rewardPeriodSeconds, ownerFeePercent, rewardChrtAmount, noFeeChrtThreshold, cancelChrtThreshold, chrtMint).accounts({
+    await program.methods.initialize(rewardPeriodSeconds, ownerFeePercent, rewardChrtAmount, noFeeChrtThreshold, cancelQuorumBps, cancelVotingPeriodSeconds, cancelGracePeriodSeconds, redistributionPolicy, owner.publicKey, chrtMint, permissionlessRewards, crankTipChrtAmount, vestingCliffSeconds, vestingDurationSeconds).accounts({
       donationService: statePda,
       owner: owner.publicKey
     }).signers([]).rpc();
@@ -62,29 +84,45 @@ describe("solana_donation", () => {
     let donationState = await program.account.donationService.fetch(statePda);
     assert(donationState.fundraisingsNum.eq(new anchor.BN(0)))
     assert(donationState.owner.equals(owner.publicKey))
+    assert(donationState.redistributionPolicy.proportional !== undefined)
+    assert(donationState.reserveFund.equals(owner.publicKey))
   });
 
   it("Test fundraising creation", async () => {
     await provider.connection.confirmTransaction(await provider.connection.requestAirdrop(fundraisingOwnerAccount.publicKey, 1 * anchor.web3.LAMPORTS_PER_SOL));
+    await provider.connection.confirmTransaction(await provider.connection.requestAirdrop(beneficiary.publicKey, 1 * anchor.web3.LAMPORTS_PER_SOL));
 
     const [donationAccount,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("state")], program.programId);
+    const [organizerPda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("organizer"), fundraisingOwnerAccount.publicKey.toBuffer()], program.programId);
 
     for (let i = 0; i < 5; i++) {
       let donationState = await program.account.donationService.fetch(donationAccount);
 
       const [fundraisingPda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("fundraising"), donationState.fundraisingsNum.toBuffer('le', 8)], program.programId);
 
-      await program.methods.createFundraising().accounts({
+      const title = `Fundraising ${i}`;
+      const metadataUri = `https://example.com/fundraisings/${i}.json`;
+      const contentHash = [...createHash("sha256").update(metadataUri).digest()];
+
+      await program.methods.createFundraising(title, metadataUri, contentHash, { medical: {} }, beneficiary.publicKey).accounts({
         owner: fundraisingOwnerAccount.publicKey,
         donationService: donationAccount,
         fundraising: fundraisingPda,
+        organizer: organizerPda,
       }).signers([fundraisingOwnerAccount]).rpc();
 
       let fundraisingState = await program.account.fundraising.fetch(fundraisingPda);
+      assert(!fundraisingState.verified);
 
       assert(fundraisingState.totalSum.eq(new anchor.BN(0)));
+      assert.equal(fundraisingState.title, title);
+      assert.equal(fundraisingState.metadataUri, metadataUri);
+      assert.deepEqual(fundraisingState.contentHash, contentHash);
+      assert.deepEqual(fundraisingState.category, { medical: {} });
       assert(fundraisingState.id.eq(new anchor.BN(i)));
       assert(fundraisingState.owner.equals(fundraisingOwnerAccount.publicKey));
+      assert(fundraisingState.beneficiary.equals(beneficiary.publicKey));
+      assert.equal(fundraisingState.pendingBeneficiary, null);
 
       let donationState1 = await program.account.donationService.fetch(donationAccount);
       assert(donationState1.fundraisingsNum.eq(new anchor.BN(i + 1)));
@@ -96,11 +134,13 @@ describe("solana_donation", () => {
 
     const [donationAccount,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("state")], program.programId);
     const [fundraisingPda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("fundraising"), fundraisingId.toBuffer('le', 8)], program.programId);
+    const [organizerPda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("organizer"), fundraisingOwnerAccount.publicKey.toBuffer()], program.programId);
     try {
-      await program.methods.createFundraising().accounts({
+      await program.methods.createFundraising("", "", new Array(32).fill(0), { other: {} }, beneficiary.publicKey).accounts({
         owner: fundraisingOwnerAccount.publicKey,
         donationService: donationAccount,
         fundraising: fundraisingPda,
+        organizer: organizerPda,
       }).signers([fundraisingOwnerAccount]).rpc();
       assert("Transaction should fail");
     } catch (e) { }
@@ -115,6 +155,8 @@ describe("solana_donation", () => {
     const [fundraisingPda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("fundraising"), fundraisingId.toBuffer('le', 8)], program.programId);
     const [donaterInfo,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("donater-info"), fundraisingId.toBuffer('le', 8), donater.publicKey.toBuffer()], program.programId);
     const [donaterTopInfo,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("global-top-info"), donater.publicKey.toBuffer()], program.programId);
+    const [donaterPendingReward,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("pending-reward"), donater.publicKey.toBuffer()], program.programId);
+    const [referrerVesting,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("vesting"), referrer.publicKey.toBuffer()], program.programId);
 
     let referrerTokenAccount = await getOrCreateAssociatedTokenAccount(
       provider.connection,
@@ -130,7 +172,9 @@ describe("solana_donation", () => {
       fundraising: fundraisingPda,
       chrtMint: chrtMint,
       donaterTopInfo: donaterTopInfo,
+      donaterPendingReward: donaterPendingReward,
       referrerChrtAccount: referrerTokenAccount.address,
+      referrerVesting: referrerVesting,
     }).signers([donater]).rpc()
 
     const fundraisingState = await program.account.fundraising.fetch(fundraisingPda);
@@ -160,12 +204,36 @@ describe("solana_donation", () => {
 
     const [fundraisingPda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("fundraising"), fundraisingId.toBuffer('le', 8)], program.programId);
 
+    const initialFundraisingState = await program.account.fundraising.fetch(fundraisingPda);
+    const initialBeneficiaryBalance = await provider.connection.getBalance(beneficiary.publicKey);
+
+    try {
+      await program.methods.withdraw(fundraisingId).accounts({
+        donationService: donationAccount,
+        fundraising: fundraisingPda,
+        beneficiary: donater.publicKey,
+        authority: donater.publicKey,
+        owner: fundraisingOwnerAccount.publicKey,
+      }).signers([donater]).rpc();
+      assert.fail("Transaction should fail");
+    } catch (e) {
+      assert.equal(e.error.errorCode.code, "InvalidBeneficiary");
+    }
+
     await program.methods.withdraw(fundraisingId).accounts({
       donationService: donationAccount,
       fundraising: fundraisingPda,
-      fundraisingOwner: fundraisingOwnerAccount.publicKey,
+      beneficiary: beneficiary.publicKey,
+      authority: fundraisingOwnerAccount.publicKey,
+      owner: fundraisingOwnerAccount.publicKey,
     }).signers([fundraisingOwnerAccount]).rpc()
 
+    const finalBeneficiaryBalance = await provider.connection.getBalance(beneficiary.publicKey);
+    assert.equal(finalBeneficiaryBalance - initialBeneficiaryBalance, initialFundraisingState.totalSum.toNumber());
+
+    const fundraisingState = await program.account.fundraising.fetch(fundraisingPda);
+    assert.deepEqual(fundraisingState.status, { withdrawn: {} });
+    assert(fundraisingState.totalSum.eqn(0));
   });
 
   it("Test fee withdrawing", async () => {
@@ -197,22 +265,30 @@ describe("solana_donation", () => {
     const [statePda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("state")], program.programId);
     const chrtToDonateAmount = noFeeChrtThreshold.add(new BN(1));
 
-    const fundraisingTokenAccount = await getOrCreateAssociatedTokenAccount(provider.connection, payer, chrtMint, fundraisingPda, true);
     const referrerTokenAccount = await getOrCreateAssociatedTokenAccount(provider.connection, payer, chrtMint, referrer.publicKey);
+    const initialChrtSupply = (await getMint(provider.connection, chrtMint)).supply;
 
-    await program.methods.donateChrt(chrtToDonateAmount, fundraisingId, true).accounts({
+    await program.methods.donateChrt(chrtToDonateAmount, fundraisingId).accounts({
       donater: referrer.publicKey,
       fundraising: fundraisingPda,
       donationService: statePda,
       donaterTokenAccount: referrerTokenAccount.address,
-      fundraisingTokenAccount: fundraisingTokenAccount.address
+      chrtMint: chrtMint
     }).signers([referrer]).rpc();
 
+    const chrtSupply = (await getMint(provider.connection, chrtMint)).supply;
+    assert(chrtSupply == initialChrtSupply - BigInt(chrtToDonateAmount.toString()));
+
+    const stateAfterBurn = await program.account.donationService.fetch(statePda);
+    assert(stateAfterBurn.totalBurnedNoFeeChrt.eq(chrtToDonateAmount));
+
     await provider.connection.confirmTransaction(await provider.connection.requestAirdrop(donater.publicKey, 1 * anchor.web3.LAMPORTS_PER_SOL));
 
     let [donationAccount,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("state")], program.programId);
     const [donaterInfo,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("donater-info"), fundraisingId.toBuffer('le', 8), donater.publicKey.toBuffer()], program.programId);
     const [donaterTopInfo,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("global-top-info"), donater.publicKey.toBuffer()], program.programId);
+    const [donaterPendingReward,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("pending-reward"), donater.publicKey.toBuffer()], program.programId);
+    const [referrerVesting,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("vesting"), referrer.publicKey.toBuffer()], program.programId);
 
     const sumToDonate = new anchor.BN(1000);
 
@@ -223,7 +299,9 @@ describe("solana_donation", () => {
       fundraising: fundraisingPda,
       chrtMint: chrtMint,
       referrerChrtAccount: referrerTokenAccount.address,
-      donaterTopInfo: donaterTopInfo
+      donaterTopInfo: donaterTopInfo,
+      donaterPendingReward: donaterPendingReward,
+      referrerVesting: referrerVesting
     }).signers([donater]).rpc()
 
     const fundraisingState = await program.account.fundraising.fetch(fundraisingPda);
@@ -233,120 +311,1583 @@ describe("solana_donation", () => {
     assert(donationState.totalDroppedFee.eq(sumToDonate.mul(ownerFeePercent).div(new BN(100))));
   });
 
-  it("Test chrt donating to cancel fundraising", async () => {
+  it("Test community cancel voting", async () => {
     const fundraisingId = fundraisingId5;
+    const proposalId = new BN(0);
+    const cancelVotesAmount = new BN(20_000);
+    const keepVotesAmount = new BN(10_000);
 
     const [statePda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("state")], program.programId);
     const [fundraisingPda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("fundraising"), fundraisingId.toBuffer('le', 8)], program.programId);
-
-    const fundraisingTokenAccount = await getOrCreateAssociatedTokenAccount(provider.connection, payer, chrtMint, fundraisingPda, true);
+    const [cancelProposalPda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("cancel-proposal"), fundraisingId.toBuffer('le', 8), proposalId.toBuffer('le', 8)], program.programId);
+    const [cancelVotePda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("cancel-vote"), cancelProposalPda.toBuffer(), referrer.publicKey.toBuffer()], program.programId);
+    const proposalTokenAccount = await getAssociatedTokenAddress(chrtMint, cancelProposalPda, true);
     const referrerTokenAccount = await getOrCreateAssociatedTokenAccount(provider.connection, payer, chrtMint, referrer.publicKey);
+    const sumToRedistribute = new BN(1001);
 
-    await program.methods.donateChrt(cancelChrtThreshold.add(new BN(1)), fundraisingId, false).accounts({
-      donater: referrer.publicKey,
-      fundraising: fundraisingPda,
+    const [donaterInfo,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("donater-info"), fundraisingId.toBuffer('le', 8), donater.publicKey.toBuffer()], program.programId);
+    const [donaterTopInfo,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("global-top-info"), donater.publicKey.toBuffer()], program.programId);
+    const [donaterPendingReward,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("pending-reward"), donater.publicKey.toBuffer()], program.programId);
+    const [referrerVesting,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("vesting"), referrer.publicKey.toBuffer()], program.programId);
+
+    await program.methods.donate(sumToRedistribute, fundraisingId).accounts({
+      donater: donater.publicKey,
+      donaterInfo: donaterInfo,
       donationService: statePda,
-      donaterTokenAccount: referrerTokenAccount.address,
-      fundraisingTokenAccount: fundraisingTokenAccount.address
-    }).signers([referrer]).rpc();
+      fundraising: fundraisingPda,
+      chrtMint: chrtMint,
+      referrerChrtAccount: referrerTokenAccount.address,
+      donaterTopInfo: donaterTopInfo,
+      donaterPendingReward: donaterPendingReward,
+      referrerVesting: referrerVesting
+    }).signers([donater]).rpc();
+
+    const initialState = await program.account.donationService.fetch(statePda);
+    const initialActiveBalancesSum = initialState.activeFundraisingBalances.reduce((sum, x) => sum.add(x.balance), new BN(0));
+
+    await program.methods.openCancelProposal(fundraisingId).accounts({
+      proposer: payer.publicKey,
+      fundraising: fundraisingPda,
+      cancelProposal: cancelProposalPda,
+      chrtMint: chrtMint,
+      donationService: statePda
+    }).signers([payer]).rpc();
+
+    const voteAccounts = {
+      voter: referrer.publicKey,
+      voterTokenAccount: referrerTokenAccount.address,
+      chrtMint: chrtMint,
+      cancelProposal: cancelProposalPda,
+      proposalTokenAccount: proposalTokenAccount,
+      cancelVote: cancelVotePda,
+      fundraising: fundraisingPda,
+      donationService: statePda
+    };
+    await program.methods.voteCancel(fundraisingId, proposalId, cancelVotesAmount).accounts(voteAccounts).signers([referrer]).rpc();
+    await program.methods.voteKeep(fundraisingId, proposalId, keepVotesAmount).accounts(voteAccounts).signers([referrer]).rpc();
+
+    try {
+      await program.methods.cancelFundraising(fundraisingId, proposalId).accounts({
+        user: payer.publicKey,
+        donationService: statePda,
+        fundraising: fundraisingPda,
+        cancelProposal: cancelProposalPda,
+        reserveFund: owner.publicKey,
+        chrtMint: chrtMint,
+        fundraisingTokenAccount: await getAssociatedTokenAddress(chrtMint, fundraisingPda, true)
+      }).signers([payer]).rpc();
+      assert.fail("Transaction should fail");
+    } catch (e) {
+      assert.equal(e.error.errorCode.code, "VotingInProgress");
+    }
+
+    await new Promise(resolve => setTimeout(resolve, (cancelVotingPeriodSeconds.toNumber() + cancelGracePeriodSeconds.toNumber() + 2) * 1000));
+
+    // A passed proposal can't be buried under a new one before it is executed
+    const [nextCancelProposalPda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("cancel-proposal"), fundraisingId.toBuffer('le', 8), proposalId.addn(1).toBuffer('le', 8)], program.programId);
+    const nextProposalAccounts = {
+      proposer: fundraisingOwnerAccount.publicKey,
+      fundraising: fundraisingPda,
+      cancelProposal: nextCancelProposalPda,
+      chrtMint: chrtMint,
+      donationService: statePda
+    };
+
+    try {
+      await program.methods.openCancelProposal(fundraisingId).accounts(nextProposalAccounts).signers([fundraisingOwnerAccount]).rpc();
+      assert.fail("Transaction should fail");
+    } catch (e) {
+      assert.equal(e.error.errorCode.code, "InvalidPreviousProposal");
+    }
+
+    try {
+      await program.methods.openCancelProposal(fundraisingId).accounts(nextProposalAccounts).remainingAccounts([
+        { pubkey: cancelProposalPda, isWritable: false, isSigner: false }
+      ]).signers([fundraisingOwnerAccount]).rpc();
+      assert.fail("Transaction should fail");
+    } catch (e) {
+      assert.equal(e.error.errorCode.code, "PassedProposalPending");
+    }
 
-    await program.methods.cancelFundraising(fundraisingId).accounts({
+    const recipientAccounts = await redistributionRecipients(fundraisingId);
+    const initialRecipientBalances = await Promise.all(recipientAccounts.map(x => provider.connection.getBalance(x.pubkey)));
+    const initialRecipientSums = await Promise.all(recipientAccounts.map(async x => (await program.account.fundraising.fetch(x.pubkey)).totalSum));
+    const netSumToRedistribute = (await program.account.fundraising.fetch(fundraisingPda)).totalSum;
+    const canceledActiveBalance = initialState.activeFundraisingBalances.find(x => x.id.eq(fundraisingId)).balance;
+    assert(netSumToRedistribute.lt(sumToRedistribute));
+
+    await program.methods.cancelFundraising(fundraisingId, proposalId).accounts({
       user: payer.publicKey,
       donationService: statePda,
-      fundraising: fundraisingPda
+      fundraising: fundraisingPda,
+      cancelProposal: cancelProposalPda,
+      reserveFund: owner.publicKey,
+      chrtMint: chrtMint,
+      fundraisingTokenAccount: await getAssociatedTokenAddress(chrtMint, fundraisingPda, true)
     }).signers([payer]).rpc();
+
+    const fundraisingState = await program.account.fundraising.fetch(fundraisingPda);
+    assert.deepEqual(fundraisingState.status, { canceled: {} });
+    assert(fundraisingState.totalSum.eqn(0));
+    assert((await program.account.cancelProposal.fetch(cancelProposalPda)).cancelVotes.eq(cancelVotesAmount));
+
+    // Cancellation only records the shares, the lamports stay with the service until claimed
+    const stateAfterCancel = await program.account.donationService.fetch(statePda);
+    const pendingSum = (state: typeof stateAfterCancel) => state.activeFundraisingBalances.reduce((sum, x) => sum.add(x.pendingRedistribution), new BN(0));
+    assert(pendingSum(stateAfterCancel).eq(pendingSum(initialState).add(netSumToRedistribute)));
+    assert.deepEqual(await Promise.all(recipientAccounts.map(x => provider.connection.getBalance(x.pubkey))), initialRecipientBalances);
+
+    for (const recipient of recipientAccounts) {
+      if (stateAfterCancel.activeFundraisingBalances.find(x => x.id.eq(recipient.id)).pendingRedistribution.gtn(0)) {
+        await program.methods.claimRedistribution(recipient.id).accounts({
+          donationService: statePda,
+          fundraising: recipient.pubkey
+        }).rpc();
+      }
+    }
+
+    const finalRecipientBalances = await Promise.all(recipientAccounts.map(x => provider.connection.getBalance(x.pubkey)));
+    const redistributedLamports = finalRecipientBalances.reduce((sum, x, i) => sum + x - initialRecipientBalances[i], 0);
+    assert.equal(redistributedLamports, netSumToRedistribute.toNumber());
+    const finalRecipientSums = await Promise.all(recipientAccounts.map(async x => (await program.account.fundraising.fetch(x.pubkey)).totalSum));
+    assert(finalRecipientSums.reduce((sum, x, i) => sum.add(x.sub(initialRecipientSums[i])), new BN(0)).eq(netSumToRedistribute));
+
+    const donationState = await program.account.donationService.fetch(statePda);
+    const activeBalancesSum = donationState.activeFundraisingBalances.reduce((sum, x) => sum.add(x.balance), new BN(0));
+    assert(activeBalancesSum.eq(initialActiveBalancesSum.sub(canceledActiveBalance).add(netSumToRedistribute)));
+    assert(donationState.totalCanceledFunds.eq(initialState.totalCanceledFunds.add(netSumToRedistribute)));
   });
 
-  it("Test fundraising top users correctness", async () => {
-    const fundraisingId = new BN(2);
+  it("Test refunding losing side votes", async () => {
+    const fundraisingId = fundraisingId5;
+    const proposalId = new BN(0);
+    const cancelVotesAmount = new BN(20_000);
+
+    const [statePda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("state")], program.programId);
+    const [cancelProposalPda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("cancel-proposal"), fundraisingId.toBuffer('le', 8), proposalId.toBuffer('le', 8)], program.programId);
+    const [cancelVotePda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("cancel-vote"), cancelProposalPda.toBuffer(), referrer.publicKey.toBuffer()], program.programId);
+    const proposalTokenAccount = await getAssociatedTokenAddress(chrtMint, cancelProposalPda, true);
+    const referrerTokenAccount = await getOrCreateAssociatedTokenAccount(provider.connection, payer, chrtMint, referrer.publicKey);
+    const initialStateBurned = (await program.account.donationService.fetch(statePda)).totalBurnedCancelChrt;
+
+    await program.methods.refundVote(fundraisingId, proposalId).accounts({
+      voter: referrer.publicKey,
+      voterTokenAccount: referrerTokenAccount.address,
+      chrtMint: chrtMint,
+      cancelProposal: cancelProposalPda,
+      proposalTokenAccount: proposalTokenAccount,
+      cancelVote: cancelVotePda,
+      donationService: statePda
+    }).signers([referrer]).rpc();
+
+    const updatedReferrerTokenAccount = await getAccount(provider.connection, referrerTokenAccount.address);
+    const updatedProposalTokenAccount = await getAccount(provider.connection, proposalTokenAccount);
+    assert(updatedReferrerTokenAccount.amount == referrerTokenAccount.amount + BigInt(10_000));
+    assert(updatedProposalTokenAccount.amount == BigInt(0));
+
+    const stateAfterBurn = await program.account.donationService.fetch(statePda);
+    assert(stateAfterBurn.totalBurnedCancelChrt.eq(initialStateBurned.add(cancelVotesAmount)));
+    assert((await provider.connection.getAccountInfo(cancelVotePda)) === null);
+  });
+
+  it("Test that withdrawn and canceled fundraisings can't be withdrawn", async () => {
+    const [statePda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("state")], program.programId);
+
+    for (const fundraisingId of [fundraisingId1, fundraisingId5]) {
+      const [fundraisingPda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("fundraising"), fundraisingId.toBuffer('le', 8)], program.programId);
+
+      try {
+        await program.methods.withdraw(fundraisingId).accounts({
+          donationService: statePda,
+          fundraising: fundraisingPda,
+          beneficiary: beneficiary.publicKey,
+          authority: fundraisingOwnerAccount.publicKey,
+          owner: fundraisingOwnerAccount.publicKey,
+        }).signers([fundraisingOwnerAccount]).rpc();
+        assert.fail("Transaction should fail");
+      } catch (e) {
+        assert.equal(e.error.errorCode.code, "FundraisingFinished");
+      }
+    }
+  });
+
+  it("Test fundraising metadata update", async () => {
+    const fundraisingId = fundraisingId3;
+    const title = "Updated fundraising with a longer title";
+    const metadataUri = "https://example.com/fundraisings/updated.json";
+    const contentHash = [...createHash("sha256").update(metadataUri).digest()];
+
+    const [statePda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("state")], program.programId);
     const [fundraisingPda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("fundraising"), fundraisingId.toBuffer('le', 8)], program.programId);
-    const [donationPda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("state")], program.programId);
 
-    await provider.connection.confirmTransaction(
-      await provider.connection.requestAirdrop(user1.publicKey, user1Donation * anchor.web3.LAMPORTS_PER_SOL)
-    );
-    await provider.connection.confirmTransaction(
-      await provider.connection.requestAirdrop(user2.publicKey, user2Donation * anchor.web3.LAMPORTS_PER_SOL)
-    );
-    await provider.connection.confirmTransaction(
-      await provider.connection.requestAirdrop(user3.publicKey, user3Donation * anchor.web3.LAMPORTS_PER_SOL)
-    );
-    await provider.connection.confirmTransaction(
-      await provider.connection.requestAirdrop(user4.publicKey, user4Donation * anchor.web3.LAMPORTS_PER_SOL)
-    );
+    await program.methods.updateFundraisingMetadata(fundraisingId, title, metadataUri, contentHash, { education: {} }).accounts({
+      authority: fundraisingOwnerAccount.publicKey,
+      fundraising: fundraisingPda,
+      donationService: statePda,
+    }).signers([fundraisingOwnerAccount]).rpc();
 
-    const referrerChrtAccount = await getOrCreateAssociatedTokenAccount(provider.connection, payer, chrtMint, referrer.publicKey);
+    const fundraisingState = await program.account.fundraising.fetch(fundraisingPda);
+    assert.equal(fundraisingState.title, title);
+    assert.equal(fundraisingState.metadataUri, metadataUri);
+    assert.deepEqual(fundraisingState.contentHash, contentHash);
+    assert.deepEqual(fundraisingState.category, { education: {} });
 
-    for (let [user, userDonation] of new Map<web3.Keypair, number>([
-      [user1, user1Donation], [user2, user2Donation], [user3, user3Donation], [user4, user4Donation]
-    ])) {
+    try {
+      await program.methods.updateFundraisingMetadata(fundraisingId, "a".repeat(65), metadataUri, contentHash, { education: {} }).accounts({
+        owner: fundraisingOwnerAccount.publicKey,
+        fundraising: fundraisingPda,
+        donationService: statePda,
+      }).signers([fundraisingOwnerAccount]).rpc();
+      assert.fail("Transaction should fail");
+    } catch (e) {
+      assert.equal(e.error.errorCode.code, "TitleTooLong");
+    }
 
-      const [userDonaterInfoPda,] = await web3.PublicKey.findProgramAddress(
-        [anchor.utils.bytes.utf8.encode("donater-info"),
-        fundraisingId.toBuffer('le', 8),
-        user.publicKey.toBuffer()], program.programId);
+    try {
+      await program.methods.updateFundraisingMetadata(fundraisingId, title, metadataUri, contentHash, { other: {} }).accounts({
+        authority: donater.publicKey,
+        fundraising: fundraisingPda,
+        donationService: statePda,
+      }).signers([donater]).rpc();
+      assert.fail("Transaction should fail");
+    } catch (e) {
+      assert.equal(e.error.errorCode.code, "NotFundingOwner");
+    }
 
-      const [userTopInfoPda,] = await web3.PublicKey.findProgramAddress(
-        [anchor.utils.bytes.utf8.encode("global-top-info"),
-        user.publicKey.toBuffer()], program.programId);
+    const [withdrawnFundraisingPda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("fundraising"), fundraisingId1.toBuffer('le', 8)], program.programId);
+    try {
+      await program.methods.updateFundraisingMetadata(fundraisingId1, title, metadataUri, contentHash, { other: {} }).accounts({
+        authority: fundraisingOwnerAccount.publicKey,
+        fundraising: withdrawnFundraisingPda,
+        donationService: statePda,
+      }).signers([fundraisingOwnerAccount]).rpc();
+      assert.fail("Transaction should fail");
+    } catch (e) {
+      assert.equal(e.error.errorCode.code, "FundraisingFinished");
+    }
+  });
 
+  it("Test two-step beneficiary change", async () => {
+    const fundraisingId = fundraisingId3;
+    const newBeneficiary = web3.Keypair.generate();
 
-      await program.methods.donate(new BN(userDonation), fundraisingId).accounts({
-        donater: user.publicKey,
-        donaterInfo: userDonaterInfoPda,
-        donaterTopInfo: userTopInfoPda,
-        donationService: donationPda,
+    const [statePda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("state")], program.programId);
+    const [fundraisingPda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("fundraising"), fundraisingId.toBuffer('le', 8)], program.programId);
+
+    try {
+      await program.methods.proposeBeneficiary(fundraisingId, newBeneficiary.publicKey).accounts({
+        owner: beneficiary.publicKey,
         fundraising: fundraisingPda,
-        chrtMint: chrtMint,
-        referrerChrtAccount: referrerChrtAccount.address
-      }).signers([user]).rpc();
+        donationService: statePda,
+      }).signers([beneficiary]).rpc();
+      assert.fail("Transaction should fail");
+    } catch (e) {
+      assert.equal(e.error.errorCode.code, "NotFundingOwner");
+    }
+
+    await program.methods.proposeBeneficiary(fundraisingId, newBeneficiary.publicKey).accounts({
+      owner: fundraisingOwnerAccount.publicKey,
+      fundraising: fundraisingPda,
+      donationService: statePda,
+    }).signers([fundraisingOwnerAccount]).rpc();
+
+    let fundraisingState = await program.account.fundraising.fetch(fundraisingPda);
+    assert(fundraisingState.beneficiary.equals(beneficiary.publicKey));
+    assert(fundraisingState.pendingBeneficiary.equals(newBeneficiary.publicKey));
+
+    try {
+      await program.methods.acceptBeneficiary(fundraisingId).accounts({
+        newBeneficiary: donater.publicKey,
+        fundraising: fundraisingPda,
+        donationService: statePda,
+      }).signers([donater]).rpc();
+      assert.fail("Transaction should fail");
+    } catch (e) {
+      assert.equal(e.error.errorCode.code, "NotPendingBeneficiary");
     }
 
+    await program.methods.acceptBeneficiary(fundraisingId).accounts({
+      newBeneficiary: newBeneficiary.publicKey,
+      fundraising: fundraisingPda,
+      donationService: statePda,
+    }).signers([newBeneficiary]).rpc();
+
+    fundraisingState = await program.account.fundraising.fetch(fundraisingPda);
+    assert(fundraisingState.beneficiary.equals(newBeneficiary.publicKey));
+    assert.equal(fundraisingState.pendingBeneficiary, null);
+  });
+
+  it("Test co-organizer roles", async () => {
+    const fundraisingId = fundraisingId3;
+    const coOrganizer = donater;
+    const title = "Updated by a co-organizer";
+    const metadataUri = "https://example.com/fundraisings/co-organizer.json";
+    const contentHash = [...createHash("sha256").update(metadataUri).digest()];
+
+    const [statePda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("state")], program.programId);
+    const [fundraisingPda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("fundraising"), fundraisingId.toBuffer('le', 8)], program.programId);
     const fundraisingState = await program.account.fundraising.fetch(fundraisingPda);
 
-    assert(fundraisingState.topDonaters[0].totalSum.eq(new BN(user4Donation)));
-    assert(fundraisingState.topDonaters[1].totalSum.eq(new BN(user2Donation)));
-    assert(fundraisingState.topDonaters[2].totalSum.eq(new BN(user3Donation)));
+    try {
+      await program.methods.setCoOrganizer(fundraisingId, coOrganizer.publicKey, true, false, false).accounts({
+        owner: coOrganizer.publicKey,
+        fundraising: fundraisingPda,
+        donationService: statePda,
+      }).signers([coOrganizer]).rpc();
+      assert.fail("Transaction should fail");
+    } catch (e) {
+      assert.equal(e.error.errorCode.code, "NotFundingOwner");
+    }
 
-    const donationServiceState = await program.account.donationService.fetch(donationPda);
-    assert(donationServiceState.topDonaters[0].totalSum.eq(new BN(user4Donation)));
-    assert(donationServiceState.topDonaters[1].totalSum.eq(new BN(user2Donation)));
-    assert(donationServiceState.topDonaters[2].totalSum.eq(new BN(user3Donation)));
+    await program.methods.setCoOrganizer(fundraisingId, coOrganizer.publicKey, true, false, false).accounts({
+      owner: fundraisingOwnerAccount.publicKey,
+      fundraising: fundraisingPda,
+      donationService: statePda,
+    }).signers([fundraisingOwnerAccount]).rpc();
+
+    let updatedFundraisingState = await program.account.fundraising.fetch(fundraisingPda);
+    assert.equal(updatedFundraisingState.coOrganizers.length, 1);
+    assert(updatedFundraisingState.coOrganizers[0].organizer.equals(coOrganizer.publicKey));
+    assert(updatedFundraisingState.coOrganizers[0].canUpdateMetadata);
+    assert(!updatedFundraisingState.coOrganizers[0].canWithdraw);
+
+    await program.methods.updateFundraisingMetadata(fundraisingId, title, metadataUri, contentHash, { community: {} }).accounts({
+      authority: coOrganizer.publicKey,
+      fundraising: fundraisingPda,
+      donationService: statePda,
+    }).signers([coOrganizer]).rpc();
+
+    updatedFundraisingState = await program.account.fundraising.fetch(fundraisingPda);
+    assert.equal(updatedFundraisingState.title, title);
+
+    try {
+      await program.methods.withdraw(fundraisingId).accounts({
+        donationService: statePda,
+        fundraising: fundraisingPda,
+        beneficiary: fundraisingState.beneficiary,
+        authority: coOrganizer.publicKey,
+        owner: fundraisingOwnerAccount.publicKey,
+      }).signers([coOrganizer]).rpc();
+      assert.fail("Transaction should fail");
+    } catch (e) {
+      assert.equal(e.error.errorCode.code, "NotFundingOwner");
+    }
+
+    await program.methods.removeCoOrganizer(fundraisingId, coOrganizer.publicKey).accounts({
+      owner: fundraisingOwnerAccount.publicKey,
+      fundraising: fundraisingPda,
+      donationService: statePda,
+    }).signers([fundraisingOwnerAccount]).rpc();
+
+    try {
+      await program.methods.updateFundraisingMetadata(fundraisingId, title, metadataUri, contentHash, { community: {} }).accounts({
+        authority: coOrganizer.publicKey,
+        fundraising: fundraisingPda,
+        donationService: statePda,
+      }).signers([coOrganizer]).rpc();
+      assert.fail("Transaction should fail");
+    } catch (e) {
+      assert.equal(e.error.errorCode.code, "NotFundingOwner");
+    }
   });
 
-  it("Test top donaters rewarding", async () => {
-    const [donationServicePda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("state")], program.programId);
-    const donationServiceState = await program.account.donationService.fetch(donationServicePda);
+  it("Test multisig withdrawal approval", async () => {
+    const fundraisingId = fundraisingId3;
+
+    const [statePda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("state")], program.programId);
+    const [fundraisingPda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("fundraising"), fundraisingId.toBuffer('le', 8)], program.programId);
+    const [withdrawalRequestPda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("withdrawal-request"), fundraisingId.toBuffer('le', 8)], program.programId);
+    const fundraisingState = await program.account.fundraising.fetch(fundraisingPda);
+
+    const withdrawAccounts = {
+      donationService: statePda,
+      fundraising: fundraisingPda,
+      beneficiary: fundraisingState.beneficiary,
+      authority: fundraisingOwnerAccount.publicKey,
+      owner: fundraisingOwnerAccount.publicKey,
+    };
+
+    try {
+      await program.methods.setWithdrawalSigners(fundraisingId, [fundraisingOwnerAccount.publicKey, donater.publicKey], 3).accounts({
+        owner: fundraisingOwnerAccount.publicKey,
+        fundraising: fundraisingPda,
+        donationService: statePda,
+      }).signers([fundraisingOwnerAccount]).rpc();
+      assert.fail("Transaction should fail");
+    } catch (e) {
+      assert.equal(e.error.errorCode.code, "InvalidWithdrawalThreshold");
+    }
 
-    const top1Wallet = await getOrCreateAssociatedTokenAccount(provider.connection, payer, chrtMint, donationServiceState.topDonaters[0].donater);
-    const top2Wallet = await getOrCreateAssociatedTokenAccount(provider.connection, payer, chrtMint, donationServiceState.topDonaters[1].donater);
-    const top3Wallet = await getOrCreateAssociatedTokenAccount(provider.connection, payer, chrtMint, donationServiceState.topDonaters[2].donater);
+    await program.methods.setWithdrawalSigners(fundraisingId, [fundraisingOwnerAccount.publicKey, donater.publicKey], 2).accounts({
+      owner: fundraisingOwnerAccount.publicKey,
+      fundraising: fundraisingPda,
+      donationService: statePda,
+    }).signers([fundraisingOwnerAccount]).rpc();
 
-    const [top1Info, ] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("global-top-info"), donationServiceState.topDonaters[0].donater.toBuffer()], program.programId);
-    const [top2Info, ] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("global-top-info"), donationServiceState.topDonaters[1].donater.toBuffer()], program.programId);
-    const [top3Info, ] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("global-top-info"), donationServiceState.topDonaters[2].donater.toBuffer()], program.programId);
+    try {
+      await program.methods.withdraw(fundraisingId).accounts(withdrawAccounts).signers([fundraisingOwnerAccount]).rpc();
+      assert.fail("Transaction should fail");
+    } catch (e) {
+      assert.equal(e.error.errorCode.code, "WithdrawalApprovalRequired");
+    }
 
-    const initialTop1ChrtAmount = top1Wallet.amount;
-    const initialTop2ChrtAmount = top2Wallet.amount;
-    const initialTop3ChrtAmount = top3Wallet.amount;
+    try {
+      await program.methods.createWithdrawalRequest(fundraisingId).accounts({
+        proposer: beneficiary.publicKey,
+        fundraising: fundraisingPda,
+        withdrawalRequest: withdrawalRequestPda,
+        donationService: statePda,
+      }).signers([beneficiary]).rpc();
+      assert.fail("Transaction should fail");
+    } catch (e) {
+      assert.equal(e.error.errorCode.code, "NotWithdrawalSigner");
+    }
 
-    await program.methods.rewardTopDonaters().accounts({
-      donationService: donationServicePda,
-      chrtMint: chrtMint,
-      top1Wallet: top1Wallet.address,
-      top2Wallet: top2Wallet.address,
-      top3Wallet: top3Wallet.address,
-      top1Info: top1Info,
-      top2Info: top2Info,
-      top3Info: top3Info
+    await program.methods.createWithdrawalRequest(fundraisingId).accounts({
+      proposer: donater.publicKey,
+      fundraising: fundraisingPda,
+      withdrawalRequest: withdrawalRequestPda,
+      donationService: statePda,
+    }).signers([donater]).rpc();
+
+    const withdrawalRequestState = await program.account.withdrawalRequest.fetch(withdrawalRequestPda);
+    assert.equal(withdrawalRequestState.approvals.length, 1);
+    assert(withdrawalRequestState.approvals[0].equals(donater.publicKey));
+
+    try {
+      await program.methods.approveWithdrawal(fundraisingId).accounts({
+        approver: donater.publicKey,
+        fundraising: fundraisingPda,
+        withdrawalRequest: withdrawalRequestPda,
+        donationService: statePda,
+      }).signers([donater]).rpc();
+      assert.fail("Transaction should fail");
+    } catch (e) {
+      assert.equal(e.error.errorCode.code, "AlreadyApproved");
+    }
+
+    try {
+      await program.methods.executeWithdrawal(fundraisingId).accounts({
+        withdraw: withdrawAccounts,
+        withdrawalRequest: withdrawalRequestPda,
+      }).signers([fundraisingOwnerAccount]).rpc();
+      assert.fail("Transaction should fail");
+    } catch (e) {
+      assert.equal(e.error.errorCode.code, "NotEnoughApprovals");
+    }
+
+    try {
+      await program.methods.setWithdrawalSigners(fundraisingId, [], 0).accounts({
+        owner: fundraisingOwnerAccount.publicKey,
+        fundraising: fundraisingPda,
+        donationService: statePda,
+      }).signers([fundraisingOwnerAccount]).rpc();
+      assert.fail("Transaction should fail");
+    } catch (e) {
+      assert.equal(e.error.errorCode.code, "WithdrawalSignersLocked");
+    }
+  });
+
+  it("Test that chrt can't be donated to a finished fundraising", async () => {
+    const fundraisingId = fundraisingId1;
+
+    const [statePda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("state")], program.programId);
+    const [fundraisingPda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("fundraising"), fundraisingId.toBuffer('le', 8)], program.programId);
+    const referrerTokenAccount = await getOrCreateAssociatedTokenAccount(provider.connection, payer, chrtMint, referrer.publicKey);
+
+    try {
+      await program.methods.donateChrt(new BN(1), fundraisingId).accounts({
+        donater: referrer.publicKey,
+        fundraising: fundraisingPda,
+        donationService: statePda,
+        donaterTokenAccount: referrerTokenAccount.address,
+        chrtMint: chrtMint
+      }).signers([referrer]).rpc();
+      assert.fail("Transaction should fail");
+    } catch (e) {
+      assert.equal(e.error.errorCode.code, "FundraisingFinished");
+    }
+  });
+
+  it("Test that zero chrt can't be donated", async () => {
+    const fundraisingId = fundraisingId2;
+
+    const [statePda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("state")], program.programId);
+    const [fundraisingPda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("fundraising"), fundraisingId.toBuffer('le', 8)], program.programId);
+    const referrerTokenAccount = await getOrCreateAssociatedTokenAccount(provider.connection, payer, chrtMint, referrer.publicKey);
+
+    try {
+      await program.methods.donateChrt(new BN(0), fundraisingId).accounts({
+        donater: referrer.publicKey,
+        fundraising: fundraisingPda,
+        donationService: statePda,
+        donaterTokenAccount: referrerTokenAccount.address,
+        chrtMint: chrtMint
+      }).signers([referrer]).rpc();
+      assert.fail("Transaction should fail");
+    } catch (e) {
+      assert.equal(e.error.errorCode.code, "ZeroChrtAmount");
+    }
+  });
+
+  it("Test emergency pause", async () => {
+    const fundraisingId = fundraisingId2;
+
+    const [statePda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("state")], program.programId);
+    const [fundraisingPda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("fundraising"), fundraisingId.toBuffer('le', 8)], program.programId);
+    const referrerTokenAccount = await getOrCreateAssociatedTokenAccount(provider.connection, payer, chrtMint, referrer.publicKey);
+
+    await program.methods.setGuardian(payer.publicKey).accounts({
+      owner: owner.publicKey,
+      donationService: statePda,
     }).rpc();
 
-    const updatedTop1Wallet = await getAccount(provider.connection, top1Wallet.address);
-    const updatedTop2Wallet = await getAccount(provider.connection, top2Wallet.address);
-    const updatedTop3Wallet = await getAccount(provider.connection, top3Wallet.address);
+    try {
+      await program.methods.pause().accounts({
+        authority: donater.publicKey,
+        donationService: statePda,
+      }).signers([donater]).rpc();
+      assert.fail("Transaction should fail");
+    } catch (e) {
+      assert.equal(e.error.errorCode.code, "NotGuardian");
+    }
+
+    await program.methods.pause().accounts({
+      authority: payer.publicKey,
+      donationService: statePda,
+    }).signers([payer]).rpc();
+
+    let donationState = await program.account.donationService.fetch(statePda);
+    assert(donationState.paused);
+
+    try {
+      await program.methods.donateChrt(new BN(1), fundraisingId).accounts({
+        donater: referrer.publicKey,
+        fundraising: fundraisingPda,
+        donationService: statePda,
+        donaterTokenAccount: referrerTokenAccount.address,
+        chrtMint: chrtMint
+      }).signers([referrer]).rpc();
+      assert.fail("Transaction should fail");
+    } catch (e) {
+      assert.equal(e.error.errorCode.code, "ServicePaused");
+    }
+
+    await program.methods.withdrawFee().accounts({
+      donationService: statePda,
+      donationServiceOwner: owner.publicKey,
+    }).rpc();
+
+    try {
+      await program.methods.unpause().accounts({
+        authority: payer.publicKey,
+        donationService: statePda,
+      }).signers([payer]).rpc();
+      assert.fail("Transaction should fail");
+    } catch (e) {
+      assert.equal(e.error.errorCode.code, "NotOwner");
+    }
+
+    await program.methods.unpause().accounts({
+      authority: owner.publicKey,
+      donationService: statePda,
+    }).rpc();
+
+    donationState = await program.account.donationService.fetch(statePda);
+    assert(!donationState.paused);
+  });
+
+  it("Test that every mutating instruction is blocked while paused", async () => {
+    const fundraisingId = fundraisingId2;
+
+    const [statePda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("state")], program.programId);
+    const [fundraisingPda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("fundraising"), fundraisingId.toBuffer('le', 8)], program.programId);
+    const [multisigFundraisingPda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("fundraising"), fundraisingId3.toBuffer('le', 8)], program.programId);
+    const [organizerPda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("organizer"), fundraisingOwnerAccount.publicKey.toBuffer()], program.programId);
+    const [withdrawalRequestPda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("withdrawal-request"), fundraisingId.toBuffer('le', 8)], program.programId);
+    const [multisigWithdrawalRequestPda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("withdrawal-request"), fundraisingId3.toBuffer('le', 8)], program.programId);
+    const [cancelProposalPda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("cancel-proposal"), fundraisingId.toBuffer('le', 8), new BN(0).toBuffer('le', 8)], program.programId);
+    const [chrtStakePda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("chrt-stake"), fundraisingId.toBuffer('le', 8), referrer.publicKey.toBuffer()], program.programId);
+    const [donaterInfo,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("donater-info"), fundraisingId.toBuffer('le', 8), donater.publicKey.toBuffer()], program.programId);
+    const [donaterTopInfo,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("global-top-info"), donater.publicKey.toBuffer()], program.programId);
+    const [donaterPendingReward,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("pending-reward"), donater.publicKey.toBuffer()], program.programId);
+    const [referrerVesting,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("vesting"), referrer.publicKey.toBuffer()], program.programId);
+    const [donaterVesting,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("vesting"), donater.publicKey.toBuffer()], program.programId);
+    const referrerTokenAccount = await getOrCreateAssociatedTokenAccount(provider.connection, payer, chrtMint, referrer.publicKey);
+    const donaterChrtAccount = await getAssociatedTokenAddress(chrtMint, donater.publicKey);
+    const fundraisingTokenAccount = await getAssociatedTokenAddress(chrtMint, fundraisingPda, true);
+
+    // Give the pending beneficiary and the withdrawal signer something to act on
+    await program.methods.proposeBeneficiary(fundraisingId, beneficiary.publicKey).accounts({
+      owner: fundraisingOwnerAccount.publicKey,
+      fundraising: fundraisingPda,
+      donationService: statePda,
+    }).signers([fundraisingOwnerAccount]).rpc();
+    await program.methods.setWithdrawalSigners(fundraisingId, [fundraisingOwnerAccount.publicKey], 1).accounts({
+      owner: fundraisingOwnerAccount.publicKey,
+      fundraising: fundraisingPda,
+      donationService: statePda,
+    }).signers([fundraisingOwnerAccount]).rpc();
+
+    await program.methods.pause().accounts({
+      authority: payer.publicKey,
+      donationService: statePda,
+    }).signers([payer]).rpc();
+
+    const donationState = await program.account.donationService.fetch(statePda);
+    const [newFundraisingPda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("fundraising"), donationState.fundraisingsNum.toBuffer('le', 8)], program.programId);
+
+    const pausedCalls: [string, () => Promise<string>][] = [
+      ["createFundraising", async () => program.methods.createFundraising("Paused", "", new Array(32).fill(0), { other: {} }, beneficiary.publicKey).accounts({
+        owner: fundraisingOwnerAccount.publicKey,
+        donationService: statePda,
+        fundraising: newFundraisingPda,
+        organizer: organizerPda,
+      }).signers([fundraisingOwnerAccount]).rpc()],
+      ["updateFundraisingMetadata", () => program.methods.updateFundraisingMetadata(fundraisingId, "Paused", "", new Array(32).fill(0), { other: {} }).accounts({
+        authority: fundraisingOwnerAccount.publicKey,
+        fundraising: fundraisingPda,
+        donationService: statePda,
+      }).signers([fundraisingOwnerAccount]).rpc()],
+      ["pauseFundraising", () => program.methods.pauseFundraising(fundraisingId).accounts({
+        authority: fundraisingOwnerAccount.publicKey,
+        fundraising: fundraisingPda,
+        donationService: statePda,
+      }).signers([fundraisingOwnerAccount]).rpc()],
+      ["setCoOrganizer", () => program.methods.setCoOrganizer(fundraisingId, donater.publicKey, true, true, true).accounts({
+        owner: fundraisingOwnerAccount.publicKey,
+        fundraising: fundraisingPda,
+        donationService: statePda,
+      }).signers([fundraisingOwnerAccount]).rpc()],
+      ["removeCoOrganizer", () => program.methods.removeCoOrganizer(fundraisingId, donater.publicKey).accounts({
+        owner: fundraisingOwnerAccount.publicKey,
+        fundraising: fundraisingPda,
+        donationService: statePda,
+      }).signers([fundraisingOwnerAccount]).rpc()],
+      ["proposeBeneficiary", () => program.methods.proposeBeneficiary(fundraisingId, donater.publicKey).accounts({
+        owner: fundraisingOwnerAccount.publicKey,
+        fundraising: fundraisingPda,
+        donationService: statePda,
+      }).signers([fundraisingOwnerAccount]).rpc()],
+      ["acceptBeneficiary", () => program.methods.acceptBeneficiary(fundraisingId).accounts({
+        newBeneficiary: beneficiary.publicKey,
+        fundraising: fundraisingPda,
+        donationService: statePda,
+      }).signers([beneficiary]).rpc()],
+      ["setWithdrawalSigners", () => program.methods.setWithdrawalSigners(fundraisingId, [], 0).accounts({
+        owner: fundraisingOwnerAccount.publicKey,
+        fundraising: fundraisingPda,
+        donationService: statePda,
+      }).signers([fundraisingOwnerAccount]).rpc()],
+      ["createWithdrawalRequest", () => program.methods.createWithdrawalRequest(fundraisingId).accounts({
+        proposer: fundraisingOwnerAccount.publicKey,
+        fundraising: fundraisingPda,
+        donationService: statePda,
+        withdrawalRequest: withdrawalRequestPda,
+      }).signers([fundraisingOwnerAccount]).rpc()],
+      ["approveWithdrawal", () => program.methods.approveWithdrawal(fundraisingId3).accounts({
+        approver: fundraisingOwnerAccount.publicKey,
+        fundraising: multisigFundraisingPda,
+        withdrawalRequest: multisigWithdrawalRequestPda,
+        donationService: statePda,
+      }).signers([fundraisingOwnerAccount]).rpc()],
+      ["donate", () => program.methods.donate(sumToDonate, fundraisingId).accounts({
+        donater: donater.publicKey,
+        donaterInfo: donaterInfo,
+        donationService: statePda,
+        fundraising: fundraisingPda,
+        chrtMint: chrtMint,
+        donaterTopInfo: donaterTopInfo,
+        donaterPendingReward: donaterPendingReward,
+        referrerChrtAccount: referrerTokenAccount.address,
+        referrerVesting: referrerVesting,
+      }).signers([donater]).rpc()],
+      ["stakeChrt", () => program.methods.stakeChrt(new BN(1), fundraisingId).accounts({
+        staker: referrer.publicKey,
+        stakerTokenAccount: referrerTokenAccount.address,
+        chrtMint: chrtMint,
+        fundraisingTokenAccount: fundraisingTokenAccount,
+        chrtStake: chrtStakePda,
+        fundraising: fundraisingPda,
+        donationService: statePda
+      }).signers([referrer]).rpc()],
+      ["openCancelProposal", () => program.methods.openCancelProposal(fundraisingId).accounts({
+        proposer: payer.publicKey,
+        fundraising: fundraisingPda,
+        cancelProposal: cancelProposalPda,
+        chrtMint: chrtMint,
+        donationService: statePda
+      }).signers([payer]).rpc()],
+      ["claimRedistribution", () => program.methods.claimRedistribution(fundraisingId).accounts({
+        donationService: statePda,
+        fundraising: fundraisingPda,
+      }).rpc()],
+      ["claimRewards", () => program.methods.claimRewards().accounts({
+        user: donater.publicKey,
+        pendingReward: donaterPendingReward,
+        donationService: statePda,
+        chrtMint: chrtMint,
+        userChrtAccount: donaterChrtAccount,
+        vestingAccount: donaterVesting
+      }).signers([donater]).rpc()],
+      ["releaseVested", () => program.methods.releaseVested().accounts({
+        user: referrer.publicKey,
+        vestingAccount: referrerVesting,
+        donationService: statePda,
+        chrtMint: chrtMint,
+        userChrtAccount: referrerTokenAccount.address
+      }).signers([referrer]).rpc()],
+      ["expireFundraising", async () => program.methods.expireFundraising(fundraisingId).accounts({
+        user: donater.publicKey,
+        donationService: statePda,
+        fundraising: fundraisingPda,
+        owner: fundraisingOwnerAccount.publicKey,
+        chrtMint: chrtMint,
+        fundraisingTokenAccount: fundraisingTokenAccount,
+      }).signers([donater]).rpc()],
+    ];
+
+    for (const [name, call] of pausedCalls) {
+      try {
+        await call();
+        assert.fail(`${name} should fail`);
+      } catch (e) {
+        assert.equal(e.error.errorCode.code, "ServicePaused", name);
+      }
+    }
+
+    await program.methods.unpause().accounts({
+      authority: owner.publicKey,
+      donationService: statePda,
+    }).rpc();
+  });
+
+  it("Test organizer pause and resume of a fundraising", async () => {
+    const fundraisingId = fundraisingId2;
+
+    const [statePda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("state")], program.programId);
+    const [fundraisingPda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("fundraising"), fundraisingId.toBuffer('le', 8)], program.programId);
+    const referrerTokenAccount = await getOrCreateAssociatedTokenAccount(provider.connection, payer, chrtMint, referrer.publicKey);
+
+    try {
+      await program.methods.pauseFundraising(fundraisingId).accounts({
+        authority: donater.publicKey,
+        fundraising: fundraisingPda,
+        donationService: statePda,
+      }).signers([donater]).rpc();
+      assert.fail("Transaction should fail");
+    } catch (e) {
+      assert.equal(e.error.errorCode.code, "NotFundingOwner");
+    }
+
+    await program.methods.pauseFundraising(fundraisingId).accounts({
+      authority: fundraisingOwnerAccount.publicKey,
+      fundraising: fundraisingPda,
+      donationService: statePda,
+    }).signers([fundraisingOwnerAccount]).rpc();
+
+    let fundraisingState = await program.account.fundraising.fetch(fundraisingPda);
+    assert(fundraisingState.isPaused);
+
+    const donationState = await program.account.donationService.fetch(statePda);
+    assert(donationState.activeFundraisingBalances.some(x => x.id.eq(fundraisingId)));
+
+    try {
+      await program.methods.donateChrt(new BN(1), fundraisingId).accounts({
+        donater: referrer.publicKey,
+        fundraising: fundraisingPda,
+        donationService: statePda,
+        donaterTokenAccount: referrerTokenAccount.address,
+        chrtMint: chrtMint
+      }).signers([referrer]).rpc();
+      assert.fail("Transaction should fail");
+    } catch (e) {
+      assert.equal(e.error.errorCode.code, "FundraisingPaused");
+    }
+
+    const [chrtStakePda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("chrt-stake"), fundraisingId.toBuffer('le', 8), referrer.publicKey.toBuffer()], program.programId);
+    try {
+      await program.methods.stakeChrt(new BN(1), fundraisingId).accounts({
+        staker: referrer.publicKey,
+        stakerTokenAccount: referrerTokenAccount.address,
+        chrtMint: chrtMint,
+        fundraisingTokenAccount: await getAssociatedTokenAddress(chrtMint, fundraisingPda, true),
+        chrtStake: chrtStakePda,
+        fundraising: fundraisingPda,
+        donationService: statePda
+      }).signers([referrer]).rpc();
+      assert.fail("Transaction should fail");
+    } catch (e) {
+      assert.equal(e.error.errorCode.code, "FundraisingPaused");
+    }
+
+    await program.methods.resumeFundraising(fundraisingId).accounts({
+      authority: fundraisingOwnerAccount.publicKey,
+      fundraising: fundraisingPda,
+      donationService: statePda,
+    }).signers([fundraisingOwnerAccount]).rpc();
+
+    fundraisingState = await program.account.fundraising.fetch(fundraisingPda);
+    assert(!fundraisingState.isPaused);
+  });
+
+  it("Test organizer appeal blocks community cancel", async () => {
+    const fundraisingId = fundraisingId2;
+    const proposalId = new BN(0);
+    const cancelVotesAmount = new BN(5_000);
+
+    const [statePda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("state")], program.programId);
+    const [fundraisingPda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("fundraising"), fundraisingId.toBuffer('le', 8)], program.programId);
+    const [cancelProposalPda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("cancel-proposal"), fundraisingId.toBuffer('le', 8), proposalId.toBuffer('le', 8)], program.programId);
+    const [cancelVotePda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("cancel-vote"), cancelProposalPda.toBuffer(), referrer.publicKey.toBuffer()], program.programId);
+    const proposalTokenAccount = await getAssociatedTokenAddress(chrtMint, cancelProposalPda, true);
+    const referrerTokenAccount = await getOrCreateAssociatedTokenAccount(provider.connection, payer, chrtMint, referrer.publicKey);
+    const organizerTokenAccount = await getOrCreateAssociatedTokenAccount(provider.connection, payer, chrtMint, fundraisingOwnerAccount.publicKey);
+
+    await transfer(provider.connection, payer, referrerTokenAccount.address, organizerTokenAccount.address, referrer, BigInt(cancelVotesAmount.toString()));
+
+    await program.methods.openCancelProposal(fundraisingId).accounts({
+      proposer: payer.publicKey,
+      fundraising: fundraisingPda,
+      cancelProposal: cancelProposalPda,
+      chrtMint: chrtMint,
+      donationService: statePda
+    }).signers([payer]).rpc();
+
+    await program.methods.voteCancel(fundraisingId, proposalId, cancelVotesAmount).accounts({
+      voter: referrer.publicKey,
+      voterTokenAccount: referrerTokenAccount.address,
+      chrtMint: chrtMint,
+      cancelProposal: cancelProposalPda,
+      proposalTokenAccount: proposalTokenAccount,
+      cancelVote: cancelVotePda,
+      fundraising: fundraisingPda,
+      donationService: statePda
+    }).signers([referrer]).rpc();
+
+    await new Promise(resolve => setTimeout(resolve, (cancelVotingPeriodSeconds.toNumber() + 1) * 1000));
+
+    const appealAccounts = {
+      organizer: fundraisingOwnerAccount.publicKey,
+      organizerTokenAccount: organizerTokenAccount.address,
+      chrtMint: chrtMint,
+      cancelProposal: cancelProposalPda,
+      proposalTokenAccount: proposalTokenAccount,
+      fundraising: fundraisingPda,
+      donationService: statePda
+    };
+    await program.methods.appealCancelProposal(fundraisingId, proposalId, cancelVotesAmount).accounts(appealAccounts).signers([fundraisingOwnerAccount]).rpc();
+
+    await new Promise(resolve => setTimeout(resolve, (cancelGracePeriodSeconds.toNumber() + 1) * 1000));
+
+    try {
+      await program.methods.cancelFundraising(fundraisingId, proposalId).accounts({
+        user: payer.publicKey,
+        donationService: statePda,
+        fundraising: fundraisingPda,
+        cancelProposal: cancelProposalPda,
+        reserveFund: owner.publicKey,
+        chrtMint: chrtMint,
+        fundraisingTokenAccount: await getAssociatedTokenAddress(chrtMint, fundraisingPda, true)
+      }).signers([payer]).rpc();
+      assert.fail("Transaction should fail");
+    } catch (e) {
+      assert.equal(e.error.errorCode.code, "ProposalNotPassed");
+    }
+
+    // Refunding the stake would unblock the proposal, so it stays locked while the fundraising is active
+    try {
+      await program.methods.withdrawAppealStake(fundraisingId, proposalId).accounts(appealAccounts).signers([fundraisingOwnerAccount]).rpc();
+      assert.fail("Transaction should fail");
+    } catch (e) {
+      assert.equal(e.error.errorCode.code, "FundraisingActive");
+    }
+
+    const updatedOrganizerTokenAccount = await getAccount(provider.connection, organizerTokenAccount.address);
+    assert(updatedOrganizerTokenAccount.amount == BigInt(0));
+    const updatedProposalTokenAccount = await getAccount(provider.connection, proposalTokenAccount);
+    assert(updatedProposalTokenAccount.amount == BigInt(cancelVotesAmount.mul(new BN(2)).toString()));
+
+    const fundraisingState = await program.account.fundraising.fetch(fundraisingPda);
+    assert.deepEqual(fundraisingState.status, { active: {} });
+  });
+
+  it("Test chrt staking", async () => {
+    const fundraisingId = fundraisingId4;
+    const chrtToStakeAmount = new BN(10);
+
+    const [statePda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("state")], program.programId);
+    const [fundraisingPda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("fundraising"), fundraisingId.toBuffer('le', 8)], program.programId);
+    const [chrtStakePda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("chrt-stake"), fundraisingId.toBuffer('le', 8), referrer.publicKey.toBuffer()], program.programId);
+
+    const fundraisingTokenAccount = await getAssociatedTokenAddress(chrtMint, fundraisingPda, true);
+    const referrerTokenAccount = await getOrCreateAssociatedTokenAccount(provider.connection, payer, chrtMint, referrer.publicKey);
+
+    await program.methods.stakeChrt(chrtToStakeAmount, fundraisingId).accounts({
+      staker: referrer.publicKey,
+      stakerTokenAccount: referrerTokenAccount.address,
+      chrtMint: chrtMint,
+      fundraisingTokenAccount: fundraisingTokenAccount,
+      chrtStake: chrtStakePda,
+      fundraising: fundraisingPda,
+      donationService: statePda
+    }).signers([referrer]).rpc();
+
+    const chrtStakeState = await program.account.chrtStake.fetch(chrtStakePda);
+    assert(chrtStakeState.amount.eq(chrtToStakeAmount));
+    assert(chrtStakeState.owner.equals(referrer.publicKey));
+
+    const fundraisingState = await program.account.fundraising.fetch(fundraisingPda);
+    assert(fundraisingState.totalNoFeeChrtSum.eq(chrtToStakeAmount));
+
+    try {
+      await program.methods.unstakeChrt(fundraisingId).accounts({
+        staker: referrer.publicKey,
+        stakerTokenAccount: referrerTokenAccount.address,
+        chrtMint: chrtMint,
+        fundraisingTokenAccount: fundraisingTokenAccount,
+        chrtStake: chrtStakePda,
+        fundraising: fundraisingPda,
+        donationService: statePda
+      }).signers([referrer]).rpc();
+      assert.fail("Transaction should fail");
+    } catch (e) {
+      assert.equal(e.error.errorCode.code, "FundraisingActive");
+    }
+  });
+
+  it("Test chrt unstaking after fundraising is finished", async () => {
+    const fundraisingId = fundraisingId4;
+
+    const [statePda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("state")], program.programId);
+    const [fundraisingPda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("fundraising"), fundraisingId.toBuffer('le', 8)], program.programId);
+    const [chrtStakePda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("chrt-stake"), fundraisingId.toBuffer('le', 8), referrer.publicKey.toBuffer()], program.programId);
+
+    const fundraisingTokenAccount = await getOrCreateAssociatedTokenAccount(provider.connection, payer, chrtMint, fundraisingPda, true);
+    const referrerTokenAccount = await getOrCreateAssociatedTokenAccount(provider.connection, payer, chrtMint, referrer.publicKey);
+
+    await program.methods.withdraw(fundraisingId).accounts({
+      donationService: statePda,
+      fundraising: fundraisingPda,
+      beneficiary: beneficiary.publicKey,
+      authority: beneficiary.publicKey,
+      owner: fundraisingOwnerAccount.publicKey,
+    }).signers([beneficiary]).rpc();
+
+    await program.methods.unstakeChrt(fundraisingId).accounts({
+      staker: referrer.publicKey,
+      stakerTokenAccount: referrerTokenAccount.address,
+      chrtMint: chrtMint,
+      fundraisingTokenAccount: fundraisingTokenAccount.address,
+      chrtStake: chrtStakePda,
+      fundraising: fundraisingPda,
+      donationService: statePda
+    }).signers([referrer]).rpc();
+
+    const updatedReferrerTokenAccount = await getAccount(provider.connection, referrerTokenAccount.address);
+    assert(updatedReferrerTokenAccount.amount == referrerTokenAccount.amount + fundraisingTokenAccount.amount);
+    assert((await provider.connection.getAccountInfo(chrtStakePda)) === null);
+  });
+
+  it("Test fundraising top users correctness", async () => {
+    const fundraisingId = new BN(2);
+    const [fundraisingPda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("fundraising"), fundraisingId.toBuffer('le', 8)], program.programId);
+    const [donationPda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("state")], program.programId);
+
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(user1.publicKey, user1Donation * anchor.web3.LAMPORTS_PER_SOL)
+    );
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(user2.publicKey, user2Donation * anchor.web3.LAMPORTS_PER_SOL)
+    );
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(user3.publicKey, user3Donation * anchor.web3.LAMPORTS_PER_SOL)
+    );
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(user4.publicKey, user4Donation * anchor.web3.LAMPORTS_PER_SOL)
+    );
+
+    const referrerChrtAccount = await getOrCreateAssociatedTokenAccount(provider.connection, payer, chrtMint, referrer.publicKey);
+    const [referrerVesting,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("vesting"), referrer.publicKey.toBuffer()], program.programId);
+
+    for (let [user, userDonation] of new Map<web3.Keypair, number>([
+      [user1, user1Donation], [user2, user2Donation], [user3, user3Donation], [user4, user4Donation]
+    ])) {
+
+      const [userDonaterInfoPda,] = await web3.PublicKey.findProgramAddress(
+        [anchor.utils.bytes.utf8.encode("donater-info"),
+        fundraisingId.toBuffer('le', 8),
+        user.publicKey.toBuffer()], program.programId);
+
+      const [userTopInfoPda,] = await web3.PublicKey.findProgramAddress(
+        [anchor.utils.bytes.utf8.encode("global-top-info"),
+        user.publicKey.toBuffer()], program.programId);
+
+      const [userPendingRewardPda,] = await web3.PublicKey.findProgramAddress(
+        [anchor.utils.bytes.utf8.encode("pending-reward"),
+        user.publicKey.toBuffer()], program.programId);
+
+      await program.methods.donate(new BN(userDonation), fundraisingId).accounts({
+        donater: user.publicKey,
+        donaterInfo: userDonaterInfoPda,
+        donaterTopInfo: userTopInfoPda,
+        donaterPendingReward: userPendingRewardPda,
+        donationService: donationPda,
+        fundraising: fundraisingPda,
+        chrtMint: chrtMint,
+        referrerChrtAccount: referrerChrtAccount.address,
+        referrerVesting: referrerVesting
+      }).signers([user]).rpc();
+    }
+
+    const fundraisingState = await program.account.fundraising.fetch(fundraisingPda);
+
+    assert(fundraisingState.topDonaters[0].totalSum.eq(new BN(user4Donation)));
+    assert(fundraisingState.topDonaters[1].totalSum.eq(new BN(user2Donation)));
+    assert(fundraisingState.topDonaters[2].totalSum.eq(new BN(user3Donation)));
+
+    const donationServiceState = await program.account.donationService.fetch(donationPda);
+    assert(donationServiceState.topDonaters[0].totalSum.eq(new BN(user4Donation)));
+    assert(donationServiceState.topDonaters[1].totalSum.eq(new BN(user2Donation)));
+    assert(donationServiceState.topDonaters[2].totalSum.eq(new BN(user3Donation)));
+  });
+
+  it("Test top donaters rewarding", async () => {
+    const [donationServicePda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("state")], program.programId);
+    const donationServiceState = await program.account.donationService.fetch(donationServicePda);
+
+    const [top1PendingReward, ] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("pending-reward"), donationServiceState.nominatedDonaters[0].donater.toBuffer()], program.programId);
+    const [top2PendingReward, ] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("pending-reward"), donationServiceState.nominatedDonaters[1].donater.toBuffer()], program.programId);
+    const [top3PendingReward, ] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("pending-reward"), donationServiceState.nominatedDonaters[2].donater.toBuffer()], program.programId);
+
+    const [top1Info, ] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("global-top-info"), donationServiceState.nominatedDonaters[0].donater.toBuffer()], program.programId);
+    const [top2Info, ] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("global-top-info"), donationServiceState.nominatedDonaters[1].donater.toBuffer()], program.programId);
+    const [top3Info, ] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("global-top-info"), donationServiceState.nominatedDonaters[2].donater.toBuffer()], program.programId);
+
+    const [rewardEpochPda, ] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("reward-epoch"), donationServiceState.rewardEpoch.toBuffer('le', 8)], program.programId);
+
+    const crankerWallet = await getOrCreateAssociatedTokenAccount(provider.connection, payer, chrtMint, payer.publicKey);
+
+    // The stale copy of a repeated account would overwrite the rewarded one
+    try {
+      await program.methods.rewardTopDonaters().accounts({
+        donationService: donationServicePda,
+        chrtMint: chrtMint,
+        top1PendingReward: top1PendingReward,
+        top2PendingReward: top2PendingReward,
+        top3PendingReward: top1PendingReward,
+        top1Info: top1Info,
+        top2Info: top2Info,
+        top3Info: top1Info,
+        rewardEpoch: rewardEpochPda,
+        crankerChrtAccount: crankerWallet.address,
+        cranker: payer.publicKey
+      }).signers([payer]).rpc();
+      assert.fail("Transaction should fail");
+    } catch (e) {
+      assert.equal(e.error.errorCode.code, "DuplicateRewardAccounts");
+    }
+
+    await program.methods.rewardTopDonaters().accounts({
+      donationService: donationServicePda,
+      chrtMint: chrtMint,
+      top1PendingReward: top1PendingReward,
+      top2PendingReward: top2PendingReward,
+      top3PendingReward: top3PendingReward,
+      top1Info: top1Info,
+      top2Info: top2Info,
+      top3Info: top3Info,
+      rewardEpoch: rewardEpochPda,
+      crankerChrtAccount: crankerWallet.address,
+      cranker: payer.publicKey
+    }).signers([payer]).rpc();
+
+    for (const pendingReward of [top1PendingReward, top2PendingReward, top3PendingReward]) {
+      const pendingRewardState = await program.account.pendingReward.fetch(pendingReward);
+      assert(pendingRewardState.amount.eq(rewardChrtAmount));
+    }
+
+    const updatedCrankerWallet = await getAccount(provider.connection, crankerWallet.address);
+    assert(updatedCrankerWallet.amount == crankerWallet.amount + BigInt(crankTipChrtAmount.toString()));
+  });
+
+  it("Test rewards claiming", async () => {
+    const [donationServicePda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("state")], program.programId);
+    const [pendingRewardPda, ] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("pending-reward"), user4.publicKey.toBuffer()], program.programId);
+    const [vestingPda, ] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("vesting"), user4.publicKey.toBuffer()], program.programId);
+    const user4ChrtAccount = await getAssociatedTokenAddress(chrtMint, user4.publicKey);
+
+    await program.methods.claimRewards().accounts({
+      user: user4.publicKey,
+      pendingReward: pendingRewardPda,
+      donationService: donationServicePda,
+      chrtMint: chrtMint,
+      userChrtAccount: user4ChrtAccount,
+      vestingAccount: vestingPda
+    }).signers([user4]).rpc();
+
+    const user4Wallet = await getAccount(provider.connection, user4ChrtAccount);
+    assert(user4Wallet.amount == BigInt(rewardChrtAmount.toString()));
+
+    const pendingRewardState = await program.account.pendingReward.fetch(pendingRewardPda);
+    assert(pendingRewardState.amount.eqn(0));
+    assert(pendingRewardState.totalClaimed.eq(rewardChrtAmount));
+  });
+
+  it("Test that nothing is released when vesting is disabled", async () => {
+    const [donationServicePda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("state")], program.programId);
+    const [vestingPda, ] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("vesting"), user4.publicKey.toBuffer()], program.programId);
+    const user4ChrtAccount = await getAssociatedTokenAddress(chrtMint, user4.publicKey);
+
+    try {
+      await program.methods.releaseVested().accounts({
+        user: user4.publicKey,
+        vestingAccount: vestingPda,
+        donationService: donationServicePda,
+        chrtMint: chrtMint,
+        userChrtAccount: user4ChrtAccount
+      }).signers([user4]).rpc();
+      assert.fail("Transaction should fail");
+    } catch (e) {
+      assert.equal(e.error.errorCode.code, "NothingToRelease");
+    }
+  });
+
+  it("Test reward epoch snapshot", async () => {
+    const [donationServicePda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("state")], program.programId);
+    const donationServiceState = await program.account.donationService.fetch(donationServicePda);
+    const [rewardEpochPda, ] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("reward-epoch"), new BN(0).toBuffer('le', 8)], program.programId);
+    const rewardEpochState = await program.account.rewardEpoch.fetch(rewardEpochPda);
+
+    assert(donationServiceState.rewardEpoch.eq(new BN(1)));
+    assert(donationServiceState.nextRewardAt.eq(rewardEpochState.rewardedAt.add(rewardPeriodSeconds)));
+    assert(rewardEpochState.epoch.eq(new BN(0)));
+    assert(rewardEpochState.rewardChrtAmount.eq(rewardChrtAmount));
+    assert(rewardEpochState.rewardedDonaters[0].donater.equals(user4.publicKey));
+    assert(rewardEpochState.rewardedDonaters[0].totalSum.eq(new BN(user4Donation)));
+  });
+
+  it("Test that the same donations are not rewarded twice", async () => {
+    const [donationServicePda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("state")], program.programId);
+    let donationServiceState = await program.account.donationService.fetch(donationServicePda);
+
+    assert(donationServiceState.nominatedDonaters[0].donater.equals(user1.publicKey));
+    assert(donationServiceState.nominatedDonaters[0].totalSum.eq(new BN(user1Donation)));
+    assert(donationServiceState.nominatedDonaters[1].donater.equals(donater.publicKey));
+    assert(donationServiceState.nominatedDonaters[2] === null);
+
+    await new Promise(resolve => setTimeout(resolve, (rewardPeriodSeconds.toNumber() + 2) * 1000));
+
+    const crankerWallet = await getOrCreateAssociatedTokenAccount(provider.connection, payer, chrtMint, payer.publicKey);
+
+    const [user1PendingReward, ] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("pending-reward"), user1.publicKey.toBuffer()], program.programId);
+    const [donaterPendingReward, ] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("pending-reward"), donater.publicKey.toBuffer()], program.programId);
+    const [user4PendingReward, ] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("pending-reward"), user4.publicKey.toBuffer()], program.programId);
+
+    const [user1Info, ] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("global-top-info"), user1.publicKey.toBuffer()], program.programId);
+    const [donaterInfo, ] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("global-top-info"), donater.publicKey.toBuffer()], program.programId);
+    const [user4Info, ] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("global-top-info"), user4.publicKey.toBuffer()], program.programId);
+    const [rewardEpochPda, ] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("reward-epoch"), donationServiceState.rewardEpoch.toBuffer('le', 8)], program.programId);
+
+    await program.methods.rewardTopDonaters().accounts({
+      donationService: donationServicePda,
+      chrtMint: chrtMint,
+      top1PendingReward: user1PendingReward,
+      top2PendingReward: donaterPendingReward,
+      top3PendingReward: user4PendingReward,
+      top1Info: user1Info,
+      top2Info: donaterInfo,
+      top3Info: user4Info,
+      rewardEpoch: rewardEpochPda,
+      crankerChrtAccount: crankerWallet.address,
+      cranker: payer.publicKey
+    }).signers([payer]).rpc();
+
+    const user1PendingRewardState = await program.account.pendingReward.fetch(user1PendingReward);
+    const user4PendingRewardState = await program.account.pendingReward.fetch(user4PendingReward);
+    assert(user1PendingRewardState.amount.eq(rewardChrtAmount));
+    assert(user4PendingRewardState.amount.eqn(0));
+
+    const rewardEpochState = await program.account.rewardEpoch.fetch(rewardEpochPda);
+    assert(rewardEpochState.rewardedDonaters[2] === null);
+
+    donationServiceState = await program.account.donationService.fetch(donationServicePda);
+    assert(donationServiceState.nominatedDonaters.every(x => x === null));
+  });
+
+  it("Test verified organizers registry", async () => {
+    const [statePda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("state")], program.programId);
+    const [organizerPda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("organizer"), fundraisingOwnerAccount.publicKey.toBuffer()], program.programId);
+
+    await program.methods.configureVerification(payer.publicKey, true).accounts({
+      owner: owner.publicKey,
+      donationService: statePda,
+    }).rpc();
+
+    let donationState = await program.account.donationService.fetch(statePda);
+    const [fundraisingPda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("fundraising"), donationState.fundraisingsNum.toBuffer('le', 8)], program.programId);
+    const createAccounts = {
+      owner: fundraisingOwnerAccount.publicKey,
+      donationService: statePda,
+      fundraising: fundraisingPda,
+      organizer: organizerPda,
+    };
+
+    try {
+      await program.methods.createFundraising("Verified", "", new Array(32).fill(0), { other: {} }, beneficiary.publicKey).accounts(createAccounts).signers([fundraisingOwnerAccount]).rpc();
+      assert.fail("Transaction should fail");
+    } catch (e) {
+      assert.equal(e.error.errorCode.code, "OrganizerNotVerified");
+    }
+
+    try {
+      await program.methods.verifyOrganizer(fundraisingOwnerAccount.publicKey, new BN(Math.floor(Date.now() / 1000) + 3600)).accounts({
+        authority: donater.publicKey,
+        donationService: statePda,
+        organizerAccount: organizerPda,
+      }).signers([donater]).rpc();
+      assert.fail("Transaction should fail");
+    } catch (e) {
+      assert.equal(e.error.errorCode.code, "NotVerifier");
+    }
+
+    await program.methods.verifyOrganizer(fundraisingOwnerAccount.publicKey, new BN(Math.floor(Date.now() / 1000) + 3600)).accounts({
+      authority: payer.publicKey,
+      donationService: statePda,
+      organizerAccount: organizerPda,
+    }).signers([payer]).rpc();
+
+    const organizerState = await program.account.organizer.fetch(organizerPda);
+    assert(organizerState.verified);
+    assert(organizerState.verifiedBy.equals(payer.publicKey));
+
+    await program.methods.createFundraising("Verified", "", new Array(32).fill(0), { other: {} }, beneficiary.publicKey).accounts(createAccounts).signers([fundraisingOwnerAccount]).rpc();
+
+    const fundraisingState = await program.account.fundraising.fetch(fundraisingPda);
+    assert(fundraisingState.verified);
+
+    await program.methods.revokeOrganizer(fundraisingOwnerAccount.publicKey).accounts({
+      authority: payer.publicKey,
+      donationService: statePda,
+      organizerAccount: organizerPda,
+    }).signers([payer]).rpc();
+
+    assert(!(await program.account.organizer.fetch(organizerPda)).verified);
+
+    const [unknownOrganizerPda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("organizer"), donater.publicKey.toBuffer()], program.programId);
+    try {
+      await program.methods.revokeOrganizer(donater.publicKey).accounts({
+        authority: payer.publicKey,
+        donationService: statePda,
+        organizerAccount: unknownOrganizerPda,
+      }).signers([payer]).rpc();
+      assert.fail("Transaction should fail");
+    } catch (e) {
+      assert.equal(e.error.errorCode.code, "AccountNotInitialized");
+    }
+
+    await program.methods.configureVerification(owner.publicKey, false).accounts({
+      owner: owner.publicKey,
+      donationService: statePda,
+    }).rpc();
+  });
+
+  it("Test creation deposit is returned on withdrawal", async () => {
+    const solDepositAmount = new BN(1_000_000);
+    const chrtDepositAmount = new BN(5);
+
+    const [statePda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("state")], program.programId);
+    const [organizerPda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("organizer"), fundraisingOwnerAccount.publicKey.toBuffer()], program.programId);
+    const ownerTokenAccount = await getOrCreateAssociatedTokenAccount(provider.connection, payer, chrtMint, fundraisingOwnerAccount.publicKey);
+    const referrerTokenAccount = await getOrCreateAssociatedTokenAccount(provider.connection, payer, chrtMint, referrer.publicKey);
+    await transfer(provider.connection, payer, referrerTokenAccount.address, ownerTokenAccount.address, referrer, BigInt(chrtDepositAmount.toString()));
+
+    for (const [depositAmount, inChrt] of [[solDepositAmount, false], [chrtDepositAmount, true]] as [BN, boolean][]) {
+      await program.methods.configureCreationDeposit(depositAmount, inChrt).accounts({
+        owner: owner.publicKey,
+        donationService: statePda,
+      }).rpc();
+
+      const donationState = await program.account.donationService.fetch(statePda);
+      const fundraisingId = donationState.fundraisingsNum;
+      const [fundraisingPda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("fundraising"), fundraisingId.toBuffer('le', 8)], program.programId);
+      // A CHRT deposit goes to the fundraising ATA, which the client creates before the fundraising itself
+      const fundraisingTokenAccount = inChrt
+        ? (await getOrCreateAssociatedTokenAccount(provider.connection, payer, chrtMint, fundraisingPda, true)).address
+        : await getAssociatedTokenAddress(chrtMint, fundraisingPda, true);
+
+      const initialOwnerBalance = await provider.connection.getBalance(fundraisingOwnerAccount.publicKey);
+      const initialOwnerChrtBalance = (await getAccount(provider.connection, ownerTokenAccount.address)).amount;
+
+      await program.methods.createFundraising("Deposit", "", new Array(32).fill(0), { other: {} }, beneficiary.publicKey).accounts({
+        owner: fundraisingOwnerAccount.publicKey,
+        donationService: statePda,
+        fundraising: fundraisingPda,
+        organizer: organizerPda,
+      }).remainingAccounts(inChrt ? [
+        { pubkey: ownerTokenAccount.address, isWritable: true, isSigner: false },
+        { pubkey: fundraisingTokenAccount, isWritable: true, isSigner: false },
+      ] : []).signers([fundraisingOwnerAccount]).rpc();
+
+      const fundraisingState = await program.account.fundraising.fetch(fundraisingPda);
+      assert(fundraisingState.depositAmount.eq(depositAmount));
+      assert.equal(fundraisingState.depositInChrt, inChrt);
+
+      const createdOwnerBalance = await provider.connection.getBalance(fundraisingOwnerAccount.publicKey);
+      const createdOwnerChrtBalance = (await getAccount(provider.connection, ownerTokenAccount.address)).amount;
+
+      await program.methods.withdraw(fundraisingId).accounts({
+        donationService: statePda,
+        fundraising: fundraisingPda,
+        beneficiary: beneficiary.publicKey,
+        authority: beneficiary.publicKey,
+        owner: fundraisingOwnerAccount.publicKey,
+      }).remainingAccounts(inChrt ? [
+        { pubkey: fundraisingTokenAccount, isWritable: true, isSigner: false },
+        { pubkey: ownerTokenAccount.address, isWritable: true, isSigner: false },
+      ] : []).signers([beneficiary]).rpc();
+
+      const finalOwnerBalance = await provider.connection.getBalance(fundraisingOwnerAccount.publicKey);
+      const finalOwnerChrtBalance = (await getAccount(provider.connection, ownerTokenAccount.address)).amount;
+
+      if (inChrt) {
+        assert(createdOwnerChrtBalance == initialOwnerChrtBalance - BigInt(depositAmount.toString()));
+        assert(finalOwnerChrtBalance == initialOwnerChrtBalance);
+      } else {
+        assert(initialOwnerBalance - createdOwnerBalance > depositAmount.toNumber());
+        assert.equal(finalOwnerBalance - createdOwnerBalance, depositAmount.toNumber());
+      }
+      assert((await program.account.fundraising.fetch(fundraisingPda)).depositAmount.eqn(0));
+    }
+
+    await program.methods.configureCreationDeposit(new BN(0), false).accounts({
+      owner: owner.publicKey,
+      donationService: statePda,
+    }).rpc();
+  });
+
+  it("Test expiry of stale empty fundraisings", async () => {
+    const depositAmount = new BN(1_000_000);
+
+    const [statePda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("state")], program.programId);
+    const [organizerPda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("organizer"), fundraisingOwnerAccount.publicKey.toBuffer()], program.programId);
+
+    await program.methods.configureCreationDeposit(depositAmount, false).accounts({
+      owner: owner.publicKey,
+      donationService: statePda,
+    }).rpc();
+
+    let donationState = await program.account.donationService.fetch(statePda);
+    const fundraisingId = donationState.fundraisingsNum;
+    const [fundraisingPda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("fundraising"), fundraisingId.toBuffer('le', 8)], program.programId);
+    const fundraisingTokenAccount = await getAssociatedTokenAddress(chrtMint, fundraisingPda, true);
+
+    await program.methods.createFundraising("Stale", "", new Array(32).fill(0), { other: {} }, beneficiary.publicKey).accounts({
+      owner: fundraisingOwnerAccount.publicKey,
+      donationService: statePda,
+      fundraising: fundraisingPda,
+      organizer: organizerPda,
+    }).signers([fundraisingOwnerAccount]).rpc();
+    // Without a CHRT deposit the fundraising ATA isn't opened at creation
+    assert.equal(await provider.connection.getAccountInfo(fundraisingTokenAccount), null);
+
+    const expireAccounts = {
+      user: donater.publicKey,
+      donationService: statePda,
+      fundraising: fundraisingPda,
+      owner: fundraisingOwnerAccount.publicKey,
+      chrtMint: chrtMint,
+      fundraisingTokenAccount: fundraisingTokenAccount,
+    };
+
+    try {
+      await program.methods.expireFundraising(fundraisingId).accounts(expireAccounts).signers([donater]).rpc();
+      assert.fail("Transaction should fail");
+    } catch (e) {
+      assert.equal(e.error.errorCode.code, "ExpiryDisabled");
+    }
+
+    await program.methods.configureExpiry(new BN(3600)).accounts({
+      owner: owner.publicKey,
+      donationService: statePda,
+    }).rpc();
+
+    try {
+      await program.methods.expireFundraising(fundraisingId).accounts(expireAccounts).signers([donater]).rpc();
+      assert.fail("Transaction should fail");
+    } catch (e) {
+      assert.equal(e.error.errorCode.code, "FundraisingNotStale");
+    }
+
+    await program.methods.configureExpiry(new BN(1)).accounts({
+      owner: owner.publicKey,
+      donationService: statePda,
+    }).rpc();
+    await new Promise(resolve => setTimeout(resolve, 2000));
+
+    donationState = await program.account.donationService.fetch(statePda);
+    const initialTotalFee = donationState.totalFee;
+    const initialOwnerBalance = await provider.connection.getBalance(fundraisingOwnerAccount.publicKey);
+
+    await program.methods.expireFundraising(fundraisingId).accounts(expireAccounts).signers([donater]).rpc();
+
+    donationState = await program.account.donationService.fetch(statePda);
+    assert(donationState.totalFee.eq(initialTotalFee.add(depositAmount)));
+    assert(!donationState.activeFundraisingBalances.some(x => x.id.eq(fundraisingId)));
+    assert(await provider.connection.getBalance(fundraisingOwnerAccount.publicKey) > initialOwnerBalance);
+    assert.equal(await program.account.fundraising.fetchNullable(fundraisingPda), null);
+    assert.equal(await provider.connection.getAccountInfo(fundraisingTokenAccount), null);
+
+    const chrtDepositAmount = new BN(5);
+    await program.methods.configureCreationDeposit(chrtDepositAmount, true).accounts({
+      owner: owner.publicKey,
+      donationService: statePda,
+    }).rpc();
+
+    donationState = await program.account.donationService.fetch(statePda);
+    const chrtFundraisingId = donationState.fundraisingsNum;
+    const [chrtFundraisingPda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("fundraising"), chrtFundraisingId.toBuffer('le', 8)], program.programId);
+    const chrtFundraisingTokenAccount = (await getOrCreateAssociatedTokenAccount(provider.connection, payer, chrtMint, chrtFundraisingPda, true)).address;
+
+    await program.methods.createFundraising("Stale CHRT", "", new Array(32).fill(0), { other: {} }, beneficiary.publicKey).accounts({
+      owner: fundraisingOwnerAccount.publicKey,
+      donationService: statePda,
+      fundraising: chrtFundraisingPda,
+      organizer: organizerPda,
+    }).remainingAccounts([
+      { pubkey: await getAssociatedTokenAddress(chrtMint, fundraisingOwnerAccount.publicKey), isWritable: true, isSigner: false },
+      { pubkey: chrtFundraisingTokenAccount, isWritable: true, isSigner: false },
+    ]).signers([fundraisingOwnerAccount]).rpc();
+    await new Promise(resolve => setTimeout(resolve, 2000));
+
+    // CHRT sent to the fundraising outside of staking must not keep it from expiring
+    const unsolicitedChrtAmount = BigInt(1);
+    const referrerTokenAccount = await getOrCreateAssociatedTokenAccount(provider.connection, payer, chrtMint, referrer.publicKey);
+    await transfer(provider.connection, payer, referrerTokenAccount.address, chrtFundraisingTokenAccount, referrer, unsolicitedChrtAmount);
+
+    const initialChrtSupply = (await getMint(provider.connection, chrtMint)).supply;
+    const initialBurnedForfeitedChrt = (await program.account.donationService.fetch(statePda)).totalBurnedForfeitedChrt;
+
+    await program.methods.expireFundraising(chrtFundraisingId).accounts({
+      ...expireAccounts,
+      fundraising: chrtFundraisingPda,
+      fundraisingTokenAccount: chrtFundraisingTokenAccount,
+    }).signers([donater]).rpc();
+
+    // The forfeited CHRT is burned instead of piling up in an account nothing can spend from
+    assert.equal((await getMint(provider.connection, chrtMint)).supply, initialChrtSupply - BigInt(chrtDepositAmount.toString()) - unsolicitedChrtAmount);
+    donationState = await program.account.donationService.fetch(statePda);
+    assert(donationState.totalBurnedForfeitedChrt.eq(initialBurnedForfeitedChrt.add(chrtDepositAmount).add(new BN(unsolicitedChrtAmount.toString()))));
+    assert.equal(await provider.connection.getAccountInfo(chrtFundraisingTokenAccount), null);
+
+    await program.methods.configureExpiry(new BN(0)).accounts({
+      owner: owner.publicKey,
+      donationService: statePda,
+    }).rpc();
+    await program.methods.configureCreationDeposit(new BN(0), false).accounts({
+      owner: owner.publicKey,
+      donationService: statePda,
+    }).rpc();
+  });
+
+  it("Test recurring donations via subscription", async () => {
+    const amount = new BN(100_000);
+    const deposit = new BN(250_000);
+    const intervalSeconds = new BN(3600);
+
+    const [statePda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("state")], program.programId);
+    const [organizerPda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("organizer"), fundraisingOwnerAccount.publicKey.toBuffer()], program.programId);
+
+    let donationState = await program.account.donationService.fetch(statePda);
+    const fundraisingId = donationState.fundraisingsNum;
+    const [fundraisingPda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("fundraising"), fundraisingId.toBuffer('le', 8)], program.programId);
+
+    await program.methods.createFundraising("Monthly", "", new Array(32).fill(0), { community: {} }, beneficiary.publicKey).accounts({
+      owner: fundraisingOwnerAccount.publicKey,
+      donationService: statePda,
+      fundraising: fundraisingPda,
+      organizer: organizerPda,
+    }).signers([fundraisingOwnerAccount]).rpc();
+
+    const [subscriptionPda,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("subscription"), fundraisingId.toBuffer('le', 8), donater.publicKey.toBuffer()], program.programId);
+    const [donaterInfo,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("donater-info"), fundraisingId.toBuffer('le', 8), donater.publicKey.toBuffer()], program.programId);
+    const [donaterTopInfo,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("global-top-info"), donater.publicKey.toBuffer()], program.programId);
+    const [donaterPendingReward,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("pending-reward"), donater.publicKey.toBuffer()], program.programId);
+    const [referrerVesting,] = await web3.PublicKey.findProgramAddress([anchor.utils.bytes.utf8.encode("vesting"), referrer.publicKey.toBuffer()], program.programId);
+    const referrerTokenAccount = await getOrCreateAssociatedTokenAccount(provider.connection, payer, chrtMint, referrer.publicKey);
+    // Only the owner of the account passed at creation is stored, payments always go to the referrer's associated account
+    const referrerAuxTokenAccount = await createAccount(provider.connection, payer, chrtMint, referrer.publicKey, web3.Keypair.generate());
+
+    await program.methods.createSubscription(fundraisingId, amount, intervalSeconds, deposit).accounts({
+      donater: donater.publicKey,
+      subscription: subscriptionPda,
+      donaterInfo: donaterInfo,
+      donaterTopInfo: donaterTopInfo,
+      donaterPendingReward: donaterPendingReward,
+      donationService: statePda,
+      fundraising: fundraisingPda,
+      chrtMint: chrtMint,
+      referrerChrtAccount: referrerAuxTokenAccount,
+      referrerVesting: referrerVesting,
+    }).signers([donater]).rpc();
+
+    assert((await program.account.subscription.fetch(subscriptionPda)).referrer.equals(referrer.publicKey));
+
+    const executeAccounts = {
+      user: payer.publicKey,
+      subscription: subscriptionPda,
+      donaterInfo: donaterInfo,
+      donaterTopInfo: donaterTopInfo,
+      donationService: statePda,
+      fundraising: fundraisingPda,
+      chrtMint: chrtMint,
+      referrer: referrer.publicKey,
+      referrerChrtAccount: referrerTokenAccount.address,
+      referrerVesting: referrerVesting,
+    };
+
+    try {
+      await program.methods.executeSubscription(fundraisingId).accounts({ ...executeAccounts, referrerChrtAccount: referrerAuxTokenAccount }).signers([payer]).rpc();
+      assert.fail("Transaction should fail");
+    } catch (e) {
+      assert.equal(e.error.errorCode.code, "ConstraintAssociated");
+    }
+
+    try {
+      await program.methods.executeSubscription(fundraisingId).accounts({ ...executeAccounts, referrer: donater.publicKey }).signers([payer]).rpc();
+      assert.fail("Transaction should fail");
+    } catch (e) {
+      assert.equal(e.error.errorCode.code, "ConstraintAddress");
+    }
+
+    await program.methods.executeSubscription(fundraisingId).accounts(executeAccounts).signers([payer]).rpc();
+
+    const fundraisingState = await program.account.fundraising.fetch(fundraisingPda);
+    assert(fundraisingState.totalDonated.eq(amount));
+    assert(fundraisingState.totalSum.eq(amount.mul(new BN(100).sub(ownerFeePercent)).div(new BN(100))));
+    assert((await program.account.donaterInfo.fetch(donaterInfo)).totalSum.eq(amount));
+    assert.equal((await getAccount(provider.connection, referrerTokenAccount.address)).amount, referrerTokenAccount.amount + BigInt(amount.muln(101).toString()));
+
+    let subscriptionState = await program.account.subscription.fetch(subscriptionPda);
+    assert(subscriptionState.balance.eq(deposit.sub(amount)));
+    assert(subscriptionState.paymentsNum.eqn(1));
+
+    try {
+      await program.methods.executeSubscription(fundraisingId).accounts(executeAccounts).signers([payer]).rpc();
+      assert.fail("Transaction should fail");
+    } catch (e) {
+      assert.equal(e.error.errorCode.code, "SubscriptionNotDue");
+    }
+
+    const initialDonaterBalance = await provider.connection.getBalance(donater.publicKey);
+
+    await program.methods.cancelSubscription(fundraisingId).accounts({
+      donater: donater.publicKey,
+      subscription: subscriptionPda,
+    }).signers([donater]).rpc();
 
-    assert(updatedTop1Wallet.amount == initialTop1ChrtAmount + BigInt(rewardChrtAmount.toString("hex")));
-    assert(updatedTop2Wallet.amount == initialTop2ChrtAmount + BigInt(rewardChrtAmount.toString("hex")));
-    assert(updatedTop3Wallet.amount == initialTop3ChrtAmount + BigInt(rewardChrtAmount.toString("hex")));
+    const finalDonaterBalance = await provider.connection.getBalance(donater.publicKey);
+    assert(finalDonaterBalance - initialDonaterBalance > deposit.sub(amount).toNumber());
+    assert.equal(await program.account.subscription.fetchNullable(subscriptionPda), null);
   });
 });